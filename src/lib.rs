@@ -0,0 +1,3 @@
+pub mod trading;
+
+pub use trading::{ executor, fees, instrument, logic, market, oms, orderbook, orderbook_manager, orders, position, private_stream, public_stream, ratelimit, retry, timesync, trade_stream, websocket };
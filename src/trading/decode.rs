@@ -0,0 +1,49 @@
+use serde::{ Deserialize, Deserializer, de::Error };
+/*
+
+Bybit sends most numbers as strings e.g. "17071.5", these helpers are used 
+with #[serde(deserialize_with = "...")] to turn them into numeric types
+
+*/
+
+pub fn f64_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    parse_f64(&value)
+}
+// Missing values and empty strings are mapped to None
+pub fn opt_f64_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+
+    match value.as_deref() {
+        None | Some("") => Ok(None),
+        Some(value) => parse_f64(value).map(Some)
+    }
+}
+
+pub fn u64_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    parse_u64(&value)
+}
+// Missing values and empty strings are mapped to None
+pub fn opt_u64_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+
+    match value.as_deref() {
+        None | Some("") => Ok(None),
+        Some(value) => parse_u64(value).map(Some)
+    }
+}
+// Parses a single stringified number outside of a derive e.g. inside a tuple
+pub fn parse_f64<E: Error>(value: &str) -> Result<f64, E> {
+    value
+        .parse::<f64>()
+        .map_err(|_| E::custom(format!("invalid float: {:?}", value)))
+}
+
+pub fn parse_u64<E: Error>(value: &str) -> Result<u64, E> {
+    value
+        .parse::<u64>()
+        .map_err(|_| E::custom(format!("invalid integer: {:?}", value)))
+}
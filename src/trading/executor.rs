@@ -1,75 +1,603 @@
+use std::fmt;
 use url::Url;
+use hmac::{ Hmac, Mac };
+use sha2::Sha256;
 use serde_json::Value;
-use tokio::runtime::Runtime;
-use std::collections::HashMap;
-use reqwest::{ Client, header::HeaderMap };
+use serde::{ Deserialize, de::DeserializeOwned };
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::{ Client, header::{ HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE } };
+use crate::trading::ratelimit::{ EndpointGroup, RateLimiter };
+use crate::trading::retry::{ Endpoint, Failover, RetryPolicy };
+use crate::trading::timesync::TimeSync;
+use crate::trading::trade_stream::{ OrderTransport, TradeSession };
+use crate::trading::websocket::RealtimeAuth;
 
+type HmacSha256 = Hmac<Sha256>;
+
+// Default window (ms) in which Bybit will accept a signed request
+pub const DEFAULT_RECV_WINDOW: u64 = 5000;
+
+// Bybit v5 retCodes callers commonly need to branch on
+pub mod ret_code {
+    pub const OK: i64 = 0;
+    pub const REQUEST_EXPIRED: i64 = 10002; // Timestamp outside of recv window
+    pub const INVALID_SIGNATURE: i64 = 10004;
+    pub const TOO_MANY_VISITS: i64 = 10006;
+    pub const IP_RATE_LIMIT: i64 = 10018;
+    pub const ORDER_NOT_EXISTS: i64 = 110001;
+    pub const INSUFFICIENT_BALANCE: i64 = 110007;
+    pub const DUPLICATE_ORDER_LINK_ID: i64 = 110072;
+}
+
+#[derive(Debug)]
+pub enum ExecutorError {
+    InvalidRequest(String), // The request could not be built or encoded
+    Transport(reqwest::Error), // Connection, dns, tls or timeout failures
+    Status { status: u16, body: String }, // Non 2xx http response
+    Decode(serde_json::Error), // Response body did not match the expected shape
+    Exchange { code: i64, msg: String }, // Bybit answered with retCode != 0
+    RateLimited { code: Option<i64>, msg: String }, // http 403/429 or a rate limit retCode
+    Stream(String), // The WebSocket trade session is disconnected or closed
+    MaybeExecuted(String), // A retry was rejected as a duplicate orderLinkId, an earlier attempt may have placed the order
+    Timeout // No WebSocket response within the request timeout, the order may still have been placed
+}
+
+impl ExecutorError {
+    // Returns the Bybit retCode if the exchange produced one
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            ExecutorError::Exchange { code, .. } => Some(*code),
+            ExecutorError::RateLimited { code, .. } => *code,
+            ExecutorError::MaybeExecuted(_) => Some(ret_code::DUPLICATE_ORDER_LINK_ID),
+            _ => None
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, ExecutorError::RateLimited { .. })
+    }
+    // Connection failures, timeouts and 5xx responses are worth another attempt
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ExecutorError::Transport(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            ExecutorError::Status { status, .. } => *status >= 500,
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for ExecutorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutorError::InvalidRequest(msg) => write!(f, "invalid request: {}", msg),
+            ExecutorError::Transport(e) => write!(f, "transport error: {}", e),
+            ExecutorError::Status { status, body } => write!(f, "http status {}: {}", status, body),
+            ExecutorError::Decode(e) => write!(f, "failed to decode response: {}", e),
+            ExecutorError::Exchange { code, msg } => write!(f, "exchange rejected request ({}): {}", code, msg),
+            ExecutorError::RateLimited { code: Some(code), msg } => write!(f, "rate limited ({}): {}", code, msg),
+            ExecutorError::RateLimited { code: None, msg } => write!(f, "rate limited: {}", msg),
+            ExecutorError::Stream(msg) => write!(f, "stream error: {}", msg),
+            ExecutorError::MaybeExecuted(msg) => write!(f, "order may already exist: {}", msg),
+            ExecutorError::Timeout => write!(f, "request timed out")
+        }
+    }
+}
+
+impl std::error::Error for ExecutorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExecutorError::Transport(e) => Some(e),
+            ExecutorError::Decode(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<reqwest::Error> for ExecutorError {
+    fn from(e: reqwest::Error) -> ExecutorError {
+        ExecutorError::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for ExecutorError {
+    fn from(e: serde_json::Error) -> ExecutorError {
+        ExecutorError::Decode(e)
+    }
+}
+
+// Bybit v5 response envelope, result is decoded into T
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiResponse<T> {
+    pub ret_code: i64,
+    pub ret_msg: String,
+    pub result: T,
+    #[serde(default)]
+    pub ret_ext_info: Value,
+    #[serde(default)]
+    pub time: u64
+}
+
+// Turns a raw http status and body into a typed response or the matching error
+pub fn decode_response<R: DeserializeOwned>(status: u16, body: &str) -> Result<ApiResponse<R>, ExecutorError> {
+    if status == 403 || status == 429 {
+        return Err(ExecutorError::RateLimited { code: None, msg: body.to_string() });
+    }
+
+    if !(200..300).contains(&status) {
+        return Err(ExecutorError::Status { status, body: body.to_string() });
+    }
+
+    let envelope = serde_json::from_str::<ApiResponse<Value>>(body)?;
+
+    decode_envelope(envelope)
+}
+
+// Decodes the result of an already parsed envelope, shared by the REST and WebSocket paths
+pub fn decode_envelope<R: DeserializeOwned>(envelope: ApiResponse<Value>) -> Result<ApiResponse<R>, ExecutorError> {
+    match envelope.ret_code {
+        ret_code::OK => {
+            let result = serde_json::from_value::<R>(envelope.result)?;
+
+            Ok(ApiResponse {
+                ret_code: envelope.ret_code,
+                ret_msg: envelope.ret_msg,
+                result,
+                ret_ext_info: envelope.ret_ext_info,
+                time: envelope.time
+            })
+        }
+
+        ret_code::TOO_MANY_VISITS | ret_code::IP_RATE_LIMIT => {
+            Err(ExecutorError::RateLimited { code: Some(envelope.ret_code), msg: envelope.ret_msg })
+        }
+
+        code => Err(ExecutorError::Exchange { code, msg: envelope.ret_msg })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete
+}
+
+impl From<Method> for reqwest::Method {
+    fn from(method: Method) -> reqwest::Method {
+        match method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Put => reqwest::Method::PUT,
+            Method::Delete => reqwest::Method::DELETE
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Payload<T> {
+    Empty,
+    Query(T), // Url encoded into the query string
+    Body(T) // Json encoded into the request body
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RequestOptions {
+    // Private requests are signed with the executor's api credentials
+    pub private: bool,
+    pub timeout: Option<Duration>,
+    pub recv_window: u64
+}
+
+impl Default for RequestOptions {
+    fn default() -> RequestOptions {
+        RequestOptions {
+            private: false,
+            timeout: None,
+            recv_window: DEFAULT_RECV_WINDOW
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct BuildRequest<'a, T> {
-    method: &'a str,
-    url: &'a str,
-    // Payload should be [[str, str]] for "GET"
-    // Payload should be HashMap for "POST"
-    payload: T, 
-    headers: HeaderMap
+    pub method: Method,
+    // Path relative to the executor's https endpoint e.g. "/v5/market/kline"
+    pub path: &'a str,
+    pub payload: Payload<T>,
+    pub headers: HeaderMap,
+    pub options: RequestOptions
+}
+
+impl<'a> BuildRequest<'a, ()> {
+
+    pub fn new(method: Method, path: &'a str) -> BuildRequest<'a, ()> {
+        BuildRequest {
+            method,
+            path,
+            payload: Payload::Empty,
+            headers: HeaderMap::new(),
+            options: RequestOptions::default()
+        }
+    }
+
+    pub fn get(path: &'a str) -> BuildRequest<'a, ()> {
+        BuildRequest::new(Method::Get, path)
+    }
+
+    pub fn post(path: &'a str) -> BuildRequest<'a, ()> {
+        BuildRequest::new(Method::Post, path)
+    }
+
+    pub fn put(path: &'a str) -> BuildRequest<'a, ()> {
+        BuildRequest::new(Method::Put, path)
+    }
+
+    pub fn delete(path: &'a str) -> BuildRequest<'a, ()> {
+        BuildRequest::new(Method::Delete, path)
+    }
+}
+
+impl<'a, T> BuildRequest<'a, T> {
+    // Sets a payload to be sent as the query string e.g. [["symbol", "BTCUSDT"]]
+    pub fn query<Q>(self, query: Q) -> BuildRequest<'a, Q> {
+        BuildRequest {
+            method: self.method,
+            path: self.path,
+            payload: Payload::Query(query),
+            headers: self.headers,
+            options: self.options
+        }
+    }
+    // Sets a payload to be sent as the json body
+    pub fn body<B>(self, body: B) -> BuildRequest<'a, B> {
+        BuildRequest {
+            method: self.method,
+            path: self.path,
+            payload: Payload::Body(body),
+            headers: self.headers,
+            options: self.options
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> BuildRequest<'a, T> {
+        self.headers.insert(name, value);
+        self
+    }
+    // Marks the request as private so it is signed before sending
+    pub fn private(mut self) -> BuildRequest<'a, T> {
+        self.options.private = true;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> BuildRequest<'a, T> {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    pub fn recv_window(mut self, recv_window: u64) -> BuildRequest<'a, T> {
+        self.options.recv_window = recv_window;
+        self
+    }
 }
 
-pub struct Executor<'a> {
-    api_key: &'a str,
-    api_secret: &'a str, 
+pub struct Executor {
+    api_key: String,
+    api_secret: String,
     https_endpoint: Url,
     https_alt_endpoint: Url,
-    client: Client
+    client: Client,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    failover: Failover,
+    time_sync: Arc<TimeSync>,
+    order_transport: OrderTransport,
+    trade_session: Option<TradeSession>
 }
 
-impl Executor<'_> {
+impl Executor {
 
-    pub fn new<'a> (api_key: &'a str, api_secret: &'a str, https_endpoint: Url, https_alt_endpoint: Url) -> Executor<'a> {
+    pub fn new(api_key: String, api_secret: String, https_endpoint: Url, https_alt_endpoint: Url) -> Executor {
         Executor {
-            api_key: api_key,
-            api_secret: api_secret,
-            https_endpoint: https_endpoint,
-            https_alt_endpoint: https_alt_endpoint,
-            client: Client::new()
+            api_key,
+            api_secret,
+            https_endpoint,
+            https_alt_endpoint,
+            client: Client::new(),
+            rate_limiter: RateLimiter::default(),
+            retry_policy: RetryPolicy::default(),
+            failover: Failover::default(),
+            time_sync: Arc::new(TimeSync::new()),
+            order_transport: OrderTransport::default(),
+            trade_session: None
         }
     }
+    // Replaces the default rate limiter e.g. to fail fast or use account specific limits
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Executor {
+        self.rate_limiter = rate_limiter;
+        self
+    }
 
-    pub async fn fetch<'a, T: serde::ser::Serialize> (&self, build_request: BuildRequest<'a, T>) -> Result<Value, reqwest::Error> {
-        match  build_request.method {
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Executor {
+        self.retry_policy = retry_policy;
+        self
+    }
+    // Replaces the default failover e.g. to change the failure threshold or cooldown
+    pub fn with_failover(mut self, failover: Failover) -> Executor {
+        self.failover = failover;
+        self
+    }
 
-            "Get" => {
-                let resp = self
-                    .client
-                    .get(build_request.url)
-                    .query(&build_request.payload)
-                    .headers(build_request.headers)
-                    .send()
-                    .await?;
+    pub fn failover(&self) -> &Failover {
+        &self.failover
+    }
+    // Order entry goes over the trade session once the transport is set to WebSocket
+    pub fn with_trade_session(mut self, trade_session: TradeSession) -> Executor {
+        self.trade_session = Some(trade_session);
+        self
+    }
 
-                println!("{:?}", resp);
-                let resp_object = resp
-                    .json::<Value>()
-                    .await?;
+    pub fn trade_session(&self) -> Option<&TradeSession> {
+        self.trade_session.as_ref()
+    }
+    // Selects REST or the WebSocket trade session for order entry
+    pub fn with_order_transport(mut self, order_transport: OrderTransport) -> Executor {
+        self.order_transport = order_transport;
+        self
+    }
 
-                Ok(resp_object)
-            }
+    pub fn order_transport(&self) -> OrderTransport {
+        self.order_transport
+    }
+    // Measured offset and latency to the exchange clock, see sync_time
+    pub fn time_sync(&self) -> &TimeSync {
+        &self.time_sync
+    }
+    // Returns the api key used by this executor
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+    // Returns the primary rest endpoint
+    pub fn https_endpoint(&self) -> &Url {
+        &self.https_endpoint
+    }
+    // Returns the alternate rest endpoint
+    pub fn https_alt_endpoint(&self) -> &Url {
+        &self.https_alt_endpoint
+    }
+    // Signs timestamp + api_key + recv_window + payload with HMAC-SHA256 (Bybit v5)
+    // payload is the query string for "GET" and the json body for "POST"
+    pub fn sign(&self, timestamp: u128, recv_window: u64, payload: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.api_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(self.api_key.as_bytes());
+        mac.update(recv_window.to_string().as_bytes());
+        mac.update(payload.as_bytes());
+
+        hex::encode(mac.finalize().into_bytes())
+    }
+    // Credentials and clock for authenticating private and trade WebSocket connections
+    pub fn realtime_auth(&self) -> RealtimeAuth {
+        RealtimeAuth::new(self.api_key.clone(), self.api_secret.clone(), self.time_sync.clone())
+    }
+    // Builds the X-BAPI-* authentication headers for a private request
+    pub fn auth_headers(&self, timestamp: u128, recv_window: u64, payload: &str) -> HeaderMap {
+        let signature = self.sign(timestamp, recv_window, payload);
+        let mut headers = HeaderMap::new();
+
+        headers.insert("X-BAPI-API-KEY", HeaderValue::from_str(&self.api_key).expect("Invalid api key"));
+        headers.insert("X-BAPI-TIMESTAMP", HeaderValue::from_str(&timestamp.to_string()).expect("Invalid timestamp"));
+        headers.insert("X-BAPI-RECV-WINDOW", HeaderValue::from(recv_window));
+        headers.insert("X-BAPI-SIGN", HeaderValue::from_str(&signature).expect("Invalid signature"));
+
+        headers
+    }
+
+    pub async fn fetch<R: DeserializeOwned, T: serde::ser::Serialize> (&self, build_request: BuildRequest<'_, T>) -> Result<ApiResponse<R>, ExecutorError> {
+        let (prepared, idempotent) = prepare(build_request, true)?;
+        let mut retries = 0;
+
+        loop {
+            let endpoint = self.failover.select();
+            let result = self.send(endpoint, &prepared).await;
 
-            "Post" => {
-                let resp = self
-                    .client
-                    .post(build_request.url)
-                    .json(&build_request.payload)
-                    .headers(build_request.headers)
-                    .send()
-                    .await?;
+            match result {
+                Err(e) if e.is_retryable() => {
+                    self.failover.record_failure(endpoint);
 
-                let resp_object = resp 
-                    .json::<Value>()
-                    .await?;
+                    if !idempotent || retries >= self.retry_policy.max_retries {
+                        return Err(e);
+                    }
 
-                Ok(resp_object)
+                    retries += 1;
+                    tokio::time::sleep(self.retry_policy.backoff(retries)).await;
+                }
+
+                // The attempt that failed may still have reached the exchange, look the order up
+                // by its orderLinkId rather than treating it as rejected
+                Err(ExecutorError::Exchange { code: ret_code::DUPLICATE_ORDER_LINK_ID, msg }) if retries > 0 => {
+                    self.failover.record_success(endpoint);
+                    return Err(ExecutorError::MaybeExecuted(msg));
+                }
+
+                result => {
+                    self.failover.record_success(endpoint);
+                    return result;
+                }
             }
-            
-            _ => todo!()
         }
     }
-}
\ No newline at end of file
+    // A single attempt that never waits, a missing rate limit permit fails straight away.
+    // For requests where timing matters more than success e.g. clock samples
+    pub(crate) async fn fetch_once<R: DeserializeOwned, T: serde::ser::Serialize> (&self, build_request: BuildRequest<'_, T>) -> Result<ApiResponse<R>, ExecutorError> {
+        let (prepared, _) = prepare(build_request, false)?;
+        let endpoint = self.failover.select();
+
+        let result = self.send(endpoint, &prepared).await;
+
+        match &result {
+            Err(e) if e.is_retryable() => self.failover.record_failure(endpoint),
+            _ => self.failover.record_success(endpoint)
+        }
+
+        result
+    }
+    // Makes a single signed attempt against one endpoint
+    async fn send<R: DeserializeOwned>(&self, endpoint: Endpoint, prepared: &PreparedRequest<'_>) -> Result<ApiResponse<R>, ExecutorError> {
+        let base = match endpoint {
+            Endpoint::Primary => &self.https_endpoint,
+            Endpoint::Alternate => &self.https_alt_endpoint
+        };
+
+        let mut url = base
+            .join(prepared.path)
+            .map_err(|e| ExecutorError::InvalidRequest(e.to_string()))?;
+
+        if !prepared.query.is_empty() {
+            url.set_query(Some(&prepared.query));
+        }
+
+        let group = EndpointGroup::from_path(prepared.path);
+        let mut headers = prepared.headers.clone();
+        let options = prepared.options;
+
+        // Wait for a permit before signing so the timestamp is not stale
+        if prepared.wait_for_permit {
+            self.rate_limiter
+                .acquire(group)
+                .await?;
+        } else {
+            self.rate_limiter
+                .try_acquire(group)
+                .map_err(|wait| ExecutorError::RateLimited {
+                    code: None,
+                    msg: format!("client side limit for {:?}, retry in {:?}", group, wait)
+                })?;
+        }
+
+        if options.private {
+            let signed_payload = prepared.body
+                .as_deref()
+                .unwrap_or(&prepared.query);
+
+            headers.extend(self.auth_headers(self.time_sync.now_ms(), options.recv_window, signed_payload));
+        }
+
+        let mut request = self
+            .client
+            .request(prepared.method.into(), url)
+            .headers(headers);
+
+        if let Some(timeout) = options.timeout {
+            request = request.timeout(timeout);
+        }
+
+        if let Some(body) = &prepared.body {
+            request = request.body(body.clone());
+        }
+
+        let resp = request
+            .send()
+            .await?;
+
+        self.rate_limiter
+            .update_from_headers(group, resp.headers(), self.time_sync.now_ms());
+
+        let status = resp.status().as_u16();
+        let body = resp
+            .text()
+            .await?;
+
+        decode_response(status, &body)
+    }
+}
+
+// A request encoded once so every retry re-signs the exact same payload
+struct PreparedRequest<'a> {
+    method: Method,
+    path: &'a str,
+    query: String,
+    body: Option<String>,
+    headers: HeaderMap,
+    options: RequestOptions,
+    // False fails on a missing rate limit permit instead of waiting for one
+    wait_for_permit: bool
+}
+
+// Encodes the payload once, also returns whether the request is safe to send twice
+fn prepare<T: serde::ser::Serialize>(build_request: BuildRequest<'_, T>, wait_for_permit: bool) -> Result<(PreparedRequest<'_>, bool), ExecutorError> {
+    let mut headers = build_request.headers;
+
+    // The signed payload is the query string for queries and the json body for bodies
+    let (query, body) = match &build_request.payload {
+        Payload::Empty => (String::new(), None),
+
+        Payload::Query(query) => {
+            let query = serde_urlencoded::to_string(query)
+                .map_err(|e| ExecutorError::InvalidRequest(e.to_string()))?;
+
+            (query, None)
+        }
+
+        Payload::Body(body) => {
+            let body = serde_json::to_string(body)
+                .map_err(|e| ExecutorError::InvalidRequest(e.to_string()))?;
+
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            (String::new(), Some(body))
+        }
+    };
+
+    // Bybit dedups new orders on orderLinkId so those POSTs are safe to send twice. A cancel or
+    // amend by orderLinkId isn't, a retry after a lost success is rejected as if it had failed
+    let idempotent = match build_request.method {
+        Method::Get => true,
+        _ => is_order_create(build_request.path) && body.as_deref().map(has_order_link_id).unwrap_or(false)
+    };
+
+    let prepared = PreparedRequest {
+        method: build_request.method,
+        path: build_request.path,
+        query,
+        body,
+        headers,
+        options: build_request.options,
+        wait_for_permit
+    };
+
+    Ok((prepared, idempotent))
+}
+
+fn is_order_create(path: &str) -> bool {
+    matches!(path, "/v5/order/create" | "/v5/order/create-batch")
+}
+
+// True if the json body carries an orderLinkId, for batches every order must carry one
+fn has_order_link_id(body: &str) -> bool {
+    let value = match serde_json::from_str::<Value>(body) {
+        Ok(value) => value,
+        Err(_) => return false
+    };
+
+    let carries_id = |order: &Value| {
+        order
+            .get("orderLinkId")
+            .and_then(Value::as_str)
+            .map(|id| !id.is_empty())
+            .unwrap_or(false)
+    };
+
+    match value.get("request").and_then(Value::as_array) {
+        Some(orders) => !orders.is_empty() && orders.iter().all(carries_id),
+        None => carries_id(&value)
+    }
+}
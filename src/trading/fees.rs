@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use serde::{ Deserialize, Serialize };
+use crate::trading::decode::f64_from_str;
+use crate::trading::executor::{ BuildRequest, Executor, ExecutorError };
+use crate::trading::market::Category;
+/*
+
+Maker and taker fee rates by symbol. Fees follow Bybit's execFee sign, positive is paid
+and negative is a rebate
+
+*/
+
+// Fractions of notional, 0.0001 is 1bp. A negative maker rate is a rebate
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct FeeRate {
+    pub maker: f64,
+    pub taker: f64
+}
+
+impl FeeRate {
+
+    pub fn new(maker: f64, taker: f64) -> FeeRate {
+        FeeRate { maker, taker }
+    }
+
+    pub fn rate(&self, is_maker: bool) -> f64 {
+        if is_maker { self.maker } else { self.taker }
+    }
+    // Fee in the quote currency for a fill of qty at price
+    pub fn fee(&self, qty: f64, price: f64, is_maker: bool) -> f64 {
+        qty.abs() * price * self.rate(is_maker)
+    }
+}
+
+// Symbols without their own rate are charged the default rate
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FeeSchedule {
+    default: FeeRate,
+    symbols: HashMap<String, FeeRate>
+}
+
+impl FeeSchedule {
+
+    pub fn new(default: FeeRate) -> FeeSchedule {
+        FeeSchedule { default, symbols: HashMap::new() }
+    }
+    // Builds a schedule from the rates returned by get_fee_rates
+    pub fn from_fee_rates(default: FeeRate, fee_rates: &[SymbolFeeRate]) -> FeeSchedule {
+        let mut schedule = FeeSchedule::new(default);
+
+        for fee_rate in fee_rates {
+            schedule.set_rate(&fee_rate.symbol, FeeRate::new(fee_rate.maker_fee_rate, fee_rate.taker_fee_rate));
+        }
+
+        schedule
+    }
+
+    pub fn with_rate(mut self, symbol: &str, rate: FeeRate) -> FeeSchedule {
+        self.set_rate(symbol, rate);
+        self
+    }
+
+    pub fn set_rate(&mut self, symbol: &str, rate: FeeRate) {
+        self.symbols.insert(symbol.to_string(), rate);
+    }
+
+    pub fn default_rate(&self) -> FeeRate {
+        self.default
+    }
+
+    pub fn rate(&self, symbol: &str) -> FeeRate {
+        self.symbols
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    pub fn fee(&self, symbol: &str, qty: f64, price: f64, is_maker: bool) -> f64 {
+        self.rate(symbol).fee(qty, price, is_maker)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolFeeRate {
+    // Empty for options, which are charged by base coin
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub base_coin: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub maker_fee_rate: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub taker_fee_rate: f64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct FeeRates {
+    pub list: Vec<SymbolFeeRate>
+}
+
+#[derive(Serialize)]
+struct FeeRateQuery<'a> {
+    category: Category,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<&'a str>
+}
+
+impl Executor {
+    // GET /v5/account/fee-rate, the account's own rates so VIP tiers and rebates are included
+    pub async fn get_fee_rates(&self, category: Category, symbol: Option<&str>) -> Result<Vec<SymbolFeeRate>, ExecutorError> {
+        let query = FeeRateQuery { category, symbol };
+
+        let resp = self
+            .fetch::<FeeRates, _>(BuildRequest::get("/v5/account/fee-rate").query(query).private())
+            .await?;
+
+        Ok(resp.result.list)
+    }
+}
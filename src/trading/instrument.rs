@@ -0,0 +1,235 @@
+use std::ops::{ Add, Sub };
+use crate::trading::executor::{ Executor, ExecutorError };
+use crate::trading::market::{ Category, Instrument };
+/*
+
+Fixed-point prices and quantities. A Price counts ticks and a Qty counts lots of one
+instrument, InstrumentSpec holds the tick and lot size from /v5/market/instruments-info
+and converts between them and the decimal values the exchange sends and expects
+
+*/
+
+// Steps with more decimals than this are rejected, it keeps every scaled value exact in an f64
+const MAX_DECIMALS: u32 = 12;
+// Absorbs float noise when a value sits on a grid point e.g. 0.3 / 0.1 = 2.9999999999999996
+const GRID_EPSILON: f64 = 1e-9;
+// Grid for symbols without a spec, eight decimals is finer than any Bybit tick or lot size.
+// Ticks and lots are i64 so it only holds prices and sizes up to about 9.2e10
+const FALLBACK_STEP: f64 = 1e-8;
+
+// Whole number of ticks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Price(pub i64);
+
+// Whole number of lots
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Qty(pub i64);
+
+impl Add<i64> for Price {
+    type Output = Price;
+
+    fn add(self, ticks: i64) -> Price {
+        Price(self.0 + ticks)
+    }
+}
+
+impl Sub<i64> for Price {
+    type Output = Price;
+
+    fn sub(self, ticks: i64) -> Price {
+        Price(self.0 - ticks)
+    }
+}
+
+impl Add for Qty {
+    type Output = Qty;
+
+    fn add(self, other: Qty) -> Qty {
+        Qty(self.0 + other.0)
+    }
+}
+
+impl Sub for Qty {
+    type Output = Qty;
+
+    fn sub(self, other: Qty) -> Qty {
+        Qty(self.0 - other.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Nearest,
+    Down, // Towards negative infinity
+    Up // Towards positive infinity
+}
+
+impl Rounding {
+
+    // None for NaN, infinite or anything outside the i64 range rather than saturating
+    fn apply(&self, steps: f64) -> Option<i64> {
+        let steps = match self {
+            Rounding::Nearest => steps.round(),
+            Rounding::Down => (steps + GRID_EPSILON).floor(),
+            Rounding::Up => (steps - GRID_EPSILON).ceil()
+        };
+
+        // i64::MAX as f64 rounds up to 2^63 which is already out of range
+        (steps >= i64::MIN as f64 && steps < i64::MAX as f64).then_some(steps as i64)
+    }
+}
+
+// One step size expressed exactly as units / 10^decimals
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    size: f64,
+    units: i64,
+    decimals: u32
+}
+
+impl Step {
+
+    fn new(size: f64) -> Option<Step> {
+        if !size.is_finite() || size <= 0.0 {
+            return None;
+        }
+
+        (0..=MAX_DECIMALS).find_map(|decimals| {
+            let scaled = size * 10f64.powi(decimals as i32);
+            let units = scaled.round();
+
+            if (scaled - units).abs() < GRID_EPSILON * scaled.max(1.0) {
+                Some(Step { size, units: units as i64, decimals })
+            } else {
+                None
+            }
+        })
+    }
+
+    fn steps(&self, value: f64, rounding: Rounding) -> Option<i64> {
+        rounding.apply(value / self.size)
+    }
+    // Integer maths then a single division, so n steps always map to the same f64. Widened to
+    // i128 so no number of steps can overflow
+    fn value(&self, steps: i64) -> f64 {
+        (steps as i128 * self.units as i128) as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    fn format(&self, steps: i64) -> String {
+        format!("{:.*}", self.decimals as usize, self.value(steps))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstrumentSpec {
+    pub symbol: String,
+    tick: Step,
+    lot: Step,
+    min_qty: Qty
+}
+
+impl InstrumentSpec {
+    // None unless both steps are positive with at most 12 decimals
+    pub fn new(symbol: &str, tick_size: f64, lot_size: f64) -> Option<InstrumentSpec> {
+        Some(InstrumentSpec {
+            symbol: symbol.to_string(),
+            tick: Step::new(tick_size)?,
+            lot: Step::new(lot_size)?,
+            min_qty: Qty(1)
+        })
+    }
+    // For books and orders whose instrument isn't known, values keep up to eight decimals
+    pub fn fallback(symbol: &str) -> InstrumentSpec {
+        InstrumentSpec::new(symbol, FALLBACK_STEP, FALLBACK_STEP)
+            .expect("fallback step is valid")
+    }
+    // Spot instruments have no qtyStep, their basePrecision is used as the lot size instead
+    pub fn from_instrument(instrument: &Instrument) -> Option<InstrumentSpec> {
+        let lot_size = instrument.lot_size_filter
+            .step()
+            .unwrap_or(instrument.lot_size_filter.min_order_qty);
+
+        let mut spec = InstrumentSpec::new(&instrument.symbol, instrument.price_filter.tick_size, lot_size)?;
+        spec.min_qty = spec.qty(instrument.lot_size_filter.min_order_qty, Rounding::Up)?.max(Qty(1));
+
+        Some(spec)
+    }
+
+    pub fn tick_size(&self) -> f64 {
+        self.tick.size
+    }
+
+    pub fn lot_size(&self) -> f64 {
+        self.lot.size
+    }
+
+    pub fn min_qty(&self) -> Qty {
+        self.min_qty
+    }
+
+    // None when the value is not finite or too large to count in ticks
+    pub fn price(&self, value: f64, rounding: Rounding) -> Option<Price> {
+        self.tick.steps(value, rounding).map(Price)
+    }
+
+    pub fn qty(&self, value: f64, rounding: Rounding) -> Option<Qty> {
+        self.lot.steps(value, rounding).map(Qty)
+    }
+
+    pub fn price_value(&self, price: Price) -> f64 {
+        self.tick.value(price.0)
+    }
+
+    pub fn qty_value(&self, qty: Qty) -> f64 {
+        self.lot.value(qty.0)
+    }
+    // Exactly tick size decimals e.g. "30000.10", the form orders are sent in
+    pub fn format_price(&self, price: Price) -> String {
+        self.tick.format(price.0)
+    }
+
+    pub fn format_qty(&self, qty: Qty) -> String {
+        self.lot.format(qty.0)
+    }
+    // Snaps a price onto the tick grid, equal inputs always give the identical f64
+    pub fn round_price(&self, value: f64, rounding: Rounding) -> Option<f64> {
+        self.price(value, rounding).map(|price| self.price_value(price))
+    }
+
+    pub fn round_qty(&self, value: f64, rounding: Rounding) -> Option<f64> {
+        self.qty(value, rounding).map(|qty| self.qty_value(qty))
+    }
+    // Quoting rounds away from the touch so a quote never crosses further than intended,
+    // bids round down and asks round up
+    pub fn bid_price(&self, value: f64) -> Option<Price> {
+        self.price(value, Rounding::Down)
+    }
+
+    pub fn ask_price(&self, value: f64) -> Option<Price> {
+        self.price(value, Rounding::Up)
+    }
+    // Rounds down so an order never exceeds the intended size, None below the minimum order qty
+    // or when the size can't be counted in lots
+    pub fn order_qty(&self, value: f64) -> Option<Qty> {
+        let qty = self.qty(value, Rounding::Down)?;
+
+        (qty >= self.min_qty).then_some(qty)
+    }
+}
+
+impl Executor {
+    // Fetches one instrument from GET /v5/market/instruments-info and builds its spec
+    pub async fn get_instrument_spec(&self, category: Category, symbol: &str) -> Result<InstrumentSpec, ExecutorError> {
+        let info = self
+            .get_instruments_info(category, Some(symbol), None)
+            .await?;
+
+        let instrument = info.list
+            .iter()
+            .find(|instrument| instrument.symbol == symbol)
+            .ok_or_else(|| ExecutorError::InvalidRequest(format!("unknown symbol {}", symbol)))?;
+
+        InstrumentSpec::from_instrument(instrument)
+            .ok_or_else(|| ExecutorError::InvalidRequest(format!("invalid tick or lot size for {}", symbol)))
+    }
+}
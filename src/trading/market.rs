@@ -0,0 +1,371 @@
+use serde::{ Deserialize, Deserializer, Serialize };
+use crate::trading::decode::{ self, f64_from_str, opt_f64_from_str, u64_from_str, opt_u64_from_str };
+use crate::trading::executor::{ BuildRequest, Executor, ExecutorError };
+/*
+
+Typed Bybit v5 market data endpoints, these are all public and unsigned
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Spot,
+    Linear,
+    Inverse,
+    Option
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Spot => "spot",
+            Category::Linear => "linear",
+            Category::Inverse => "inverse",
+            Category::Option => "option"
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interval {
+    #[serde(rename = "1")]
+    Min1,
+    #[serde(rename = "3")]
+    Min3,
+    #[serde(rename = "5")]
+    Min5,
+    #[serde(rename = "15")]
+    Min15,
+    #[serde(rename = "30")]
+    Min30,
+    #[serde(rename = "60")]
+    Hour1,
+    #[serde(rename = "120")]
+    Hour2,
+    #[serde(rename = "240")]
+    Hour4,
+    #[serde(rename = "360")]
+    Hour6,
+    #[serde(rename = "720")]
+    Hour12,
+    #[serde(rename = "D")]
+    Day,
+    #[serde(rename = "W")]
+    Week,
+    #[serde(rename = "M")]
+    Month
+}
+
+impl Interval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::Min1 => "1",
+            Interval::Min3 => "3",
+            Interval::Min5 => "5",
+            Interval::Min15 => "15",
+            Interval::Min30 => "30",
+            Interval::Hour1 => "60",
+            Interval::Hour2 => "120",
+            Interval::Hour4 => "240",
+            Interval::Hour6 => "360",
+            Interval::Hour12 => "720",
+            Interval::Day => "D",
+            Interval::Week => "W",
+            Interval::Month => "M"
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell
+}
+
+// Bybit sends klines as ["startTime", "open", "high", "low", "close", "volume", "turnover"]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kline {
+    pub start_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub turnover: f64
+}
+
+impl<'de> Deserialize<'de> for Kline {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Kline, D::Error> {
+        let (start_time, open, high, low, close, volume, turnover) =
+            <(String, String, String, String, String, String, String)>::deserialize(deserializer)?;
+
+        Ok(Kline {
+            start_time: decode::parse_u64(&start_time)?,
+            open: decode::parse_f64(&open)?,
+            high: decode::parse_f64(&high)?,
+            low: decode::parse_f64(&low)?,
+            close: decode::parse_f64(&close)?,
+            volume: decode::parse_f64(&volume)?,
+            turnover: decode::parse_f64(&turnover)?
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Klines {
+    pub category: Category,
+    pub symbol: String,
+    // Sorted newest first
+    pub list: Vec<Kline>
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker {
+    pub symbol: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub last_price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub bid1_price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub bid1_size: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub ask1_price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub ask1_size: f64,
+    #[serde(default, rename = "prevPrice24h", deserialize_with = "opt_f64_from_str")]
+    pub prev_price_24h: Option<f64>,
+    #[serde(default, rename = "price24hPcnt", deserialize_with = "opt_f64_from_str")]
+    pub price_24h_pcnt: Option<f64>,
+    #[serde(default, rename = "highPrice24h", deserialize_with = "opt_f64_from_str")]
+    pub high_price_24h: Option<f64>,
+    #[serde(default, rename = "lowPrice24h", deserialize_with = "opt_f64_from_str")]
+    pub low_price_24h: Option<f64>,
+    #[serde(default, rename = "turnover24h", deserialize_with = "opt_f64_from_str")]
+    pub turnover_24h: Option<f64>,
+    #[serde(default, rename = "volume24h", deserialize_with = "opt_f64_from_str")]
+    pub volume_24h: Option<f64>,
+    // Derivatives only
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub index_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub mark_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub open_interest: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub funding_rate: Option<f64>,
+    #[serde(default, deserialize_with = "opt_u64_from_str")]
+    pub next_funding_time: Option<u64>
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Tickers {
+    pub category: Category,
+    pub list: Vec<Ticker>
+}
+
+// Bybit sends price levels as ["price", "size"]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Level {
+    pub price: f64,
+    pub size: f64
+}
+
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Level, D::Error> {
+        let (price, size) = <(String, String)>::deserialize(deserializer)?;
+
+        Ok(Level {
+            price: decode::parse_f64(&price)?,
+            size: decode::parse_f64(&size)?
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct OrderbookSnapshot {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    // Best bid first
+    #[serde(rename = "b")]
+    pub bids: Vec<Level>,
+    // Best ask first
+    #[serde(rename = "a")]
+    pub asks: Vec<Level>,
+    pub ts: u64,
+    #[serde(rename = "u")]
+    pub update_id: u64,
+    #[serde(default)]
+    pub seq: u64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicTrade {
+    pub exec_id: String,
+    pub symbol: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub size: f64,
+    pub side: Side,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub time: u64,
+    #[serde(default)]
+    pub is_block_trade: bool
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct RecentTrades {
+    pub category: Category,
+    pub list: Vec<PublicTrade>
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceFilter {
+    // Spot instruments only send the tick size
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub min_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub max_price: Option<f64>,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub tick_size: f64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotSizeFilter {
+    #[serde(deserialize_with = "f64_from_str")]
+    pub min_order_qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub max_order_qty: f64,
+    // Derivatives send qtyStep, spot sends basePrecision instead
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub qty_step: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub base_precision: Option<f64>
+}
+
+impl LotSizeFilter {
+    // Returns the smallest quantity increment for the instrument
+    pub fn step(&self) -> Option<f64> {
+        self.qty_step.or(self.base_precision)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instrument {
+    pub symbol: String,
+    pub status: String,
+    pub base_coin: String,
+    pub quote_coin: String,
+    pub price_filter: PriceFilter,
+    pub lot_size_filter: LotSizeFilter
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstrumentsInfo {
+    pub category: Category,
+    pub list: Vec<Instrument>,
+    // Pass back into get_instruments_info to fetch the next page
+    #[serde(default)]
+    pub next_page_cursor: String
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTime {
+    #[serde(deserialize_with = "u64_from_str")]
+    pub time_second: u64,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub time_nano: u64
+}
+
+#[derive(Serialize)]
+struct KlineQuery<'a> {
+    category: Category,
+    symbol: &'a str,
+    interval: Interval,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>
+}
+
+#[derive(Serialize)]
+struct SymbolQuery<'a> {
+    category: Category,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<&'a str>
+}
+
+impl Executor {
+    // GET /v5/market/time
+    pub async fn get_server_time(&self) -> Result<ServerTime, ExecutorError> {
+        let resp = self
+            .fetch::<ServerTime, _>(BuildRequest::get("/v5/market/time"))
+            .await?;
+
+        Ok(resp.result)
+    }
+    // GET /v5/market/kline
+    pub async fn get_klines(&self, category: Category, symbol: &str, interval: Interval, start: Option<u64>, end: Option<u64>, limit: Option<u32>) -> Result<Klines, ExecutorError> {
+        let query = KlineQuery { category, symbol, interval, start, end, limit };
+
+        let resp = self
+            .fetch::<Klines, _>(BuildRequest::get("/v5/market/kline").query(query))
+            .await?;
+
+        Ok(resp.result)
+    }
+    // GET /v5/market/tickers, symbol = None returns every ticker in the category
+    pub async fn get_tickers(&self, category: Category, symbol: Option<&str>) -> Result<Tickers, ExecutorError> {
+        let query = SymbolQuery { category, symbol, limit: None, cursor: None };
+
+        let resp = self
+            .fetch::<Tickers, _>(BuildRequest::get("/v5/market/tickers").query(query))
+            .await?;
+
+        Ok(resp.result)
+    }
+    // GET /v5/market/orderbook
+    pub async fn get_orderbook(&self, category: Category, symbol: &str, limit: Option<u32>) -> Result<OrderbookSnapshot, ExecutorError> {
+        let query = SymbolQuery { category, symbol: Some(symbol), limit, cursor: None };
+
+        let resp = self
+            .fetch::<OrderbookSnapshot, _>(BuildRequest::get("/v5/market/orderbook").query(query))
+            .await?;
+
+        Ok(resp.result)
+    }
+    // GET /v5/market/recent-trade
+    pub async fn get_recent_trades(&self, category: Category, symbol: &str, limit: Option<u32>) -> Result<RecentTrades, ExecutorError> {
+        let query = SymbolQuery { category, symbol: Some(symbol), limit, cursor: None };
+
+        let resp = self
+            .fetch::<RecentTrades, _>(BuildRequest::get("/v5/market/recent-trade").query(query))
+            .await?;
+
+        Ok(resp.result)
+    }
+    // GET /v5/market/instruments-info
+    pub async fn get_instruments_info(&self, category: Category, symbol: Option<&str>, cursor: Option<&str>) -> Result<InstrumentsInfo, ExecutorError> {
+        let query = SymbolQuery { category, symbol, limit: None, cursor };
+
+        let resp = self
+            .fetch::<InstrumentsInfo, _>(BuildRequest::get("/v5/market/instruments-info").query(query))
+            .await?;
+
+        Ok(resp.result)
+    }
+}
@@ -0,0 +1,18 @@
+pub(crate) mod decode;
+pub mod executor;
+pub mod fees;
+pub mod instrument;
+pub mod logic;
+pub mod market;
+pub mod oms;
+pub mod orderbook;
+pub mod orderbook_manager;
+pub mod orders;
+pub mod position;
+pub mod private_stream;
+pub mod public_stream;
+pub mod ratelimit;
+pub mod retry;
+pub mod timesync;
+pub mod trade_stream;
+pub mod websocket;
@@ -1,170 +1,671 @@
+use std::fmt;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{ BTreeMap, BTreeSet, HashMap };
+use std::ops::{ Bound, RangeBounds };
+use crate::trading::instrument::{ InstrumentSpec, Price, Qty, Rounding };
+use crate::trading::market::Side;
+use crate::trading::private_stream::{ self, OrderUpdate, PrivateEvent };
 /*
 
-This module aims to create a localized order management system for 
+This module aims to create a localized order management system for
 tracking and analyzing trading inventory
 
 */
 
-pub mod trading {
-	type OrderMap = RefCell<HashMap<String, Order>>;
+pub type OrderMap = RefCell<HashMap<String, Order>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OrderState {
+	New, // Created locally, not sent yet
+	PendingNew, // Sent or acknowledged, not confirmed by the order stream yet
+	Active,
+	PartiallyFilled,
+	Filled,
+	PendingCancel, // Cancel sent, the order can still fill until it's confirmed
+	Cancelled,
+	Rejected,
+	Expired
+}
 
-	enum AltReturn<'a> {
-		SomeOrder((&'a OrderMap, Order)),
-		SomeOrderId((&'a OrderMap, String))
+impl OrderState {
+	// Not yet confirmed working on the exchange
+	pub fn is_pending(&self) -> bool {
+		matches!(self, OrderState::New | OrderState::PendingNew)
+	}
+	// Resting on the exchange and able to trade
+	pub fn is_working(&self) -> bool {
+		matches!(self, OrderState::Active | OrderState::PartiallyFilled | OrderState::PendingCancel)
+	}
+	// No further transitions are possible
+	pub fn is_terminal(&self) -> bool {
+		matches!(self, OrderState::Filled | OrderState::Cancelled | OrderState::Rejected | OrderState::Expired)
+	}
+	// Staying in the same state is allowed until the order is closed e.g. amends or more fills
+	pub fn can_transition_to(&self, next: OrderState) -> bool {
+
+		use OrderState::*;
+
+		match (*self, next) {
+			(from, to) if from == to => !from.is_terminal(),
+			(New, PendingNew | Rejected | Cancelled) => true,
+			(PendingNew, Active | PartiallyFilled | Filled | Rejected | Cancelled | Expired) => true,
+			(Active, PartiallyFilled | Filled | PendingCancel | Cancelled | Expired) => true,
+			(PartiallyFilled, Filled | PendingCancel | Cancelled | Expired) => true,
+			// A rejected cancel puts the order back to working
+			(PendingCancel, Active | PartiallyFilled | Filled | Cancelled | Expired) => true,
+			_ => false
+		}
 	}
+}
+
+// How the exchange's order status maps onto the local lifecycle
+impl From<private_stream::OrderStatus> for OrderState {
+	fn from(status: private_stream::OrderStatus) -> OrderState {
 
-	#[derive(Clone)]
-	enum OrderPosition {
-		BuySide(Order), // Use when you need the order returned
-		SellSide(Order), // Use when you need the order returned
-		BuySideId(String), // Use when you need the order_id returned
-		SellSideId(String) // Use when you need the order_id returned
+		use private_stream::OrderStatus as Exchange;
+
+		match status {
+			Exchange::New | Exchange::Untriggered | Exchange::Triggered => OrderState::Active,
+			Exchange::PartiallyFilled => OrderState::PartiallyFilled,
+			Exchange::Filled => OrderState::Filled,
+			Exchange::Cancelled | Exchange::PartiallyFilledCanceled | Exchange::Deactivated => OrderState::Cancelled,
+			Exchange::Rejected => OrderState::Rejected
+		}
 	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OmsError {
+	UnknownOrder(String), // No tracked order has this id
+	DuplicateOrder(String), // An order with this id is already tracked
+	InvalidVariant { id: String, side: Side, state: OrderState }, // Order doesn't match the side and state it's filed under
+	InvalidTransition { id: String, from: OrderState, to: OrderState },
+	InvalidFill { id: String, qty: Qty, leaves_qty: Qty }, // Fill is not positive or larger than what's left
+	OutOfRange { id: String, value: f64 }, // A price or quantity that isn't finite or doesn't fit the order's grid
+	Closed { id: String, state: OrderState } // Filled, cancelled, rejected and expired orders aren't tracked
+}
 
-	#[derive(Clone)]
-	enum OrderStatus {
-		Pending(OrderPosition),
-		Active(OrderPosition)
+impl fmt::Display for OmsError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			OmsError::UnknownOrder(id) => write!(f, "unknown order {}", id),
+			OmsError::DuplicateOrder(id) => write!(f, "order {} is already tracked", id),
+			OmsError::InvalidVariant { id, side, state } => write!(f, "order {} can't be filed as {:?} {:?}", id, side, state),
+			OmsError::InvalidTransition { id, from, to } => write!(f, "order {} can't go from {:?} to {:?}", id, from, to),
+			OmsError::InvalidFill { id, qty, leaves_qty } => write!(f, "invalid fill of {} lots for order {} with {} left", qty.0, id, leaves_qty.0),
+			OmsError::OutOfRange { id, value } => write!(f, "{} is out of range for order {}", value, id),
+			OmsError::Closed { id, state } => write!(f, "order {} is already {:?}", id, state)
+		}
 	}
+}
 
-	#[derive(Debug, PartialEq, Clone)]
-	struct Order {
-		id: String,
-		price: f64,
-		qty: f64,
-		position_idx: u8,
-		created_time: i32,
-		updated_time: i32,
+impl std::error::Error for OmsError {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Order {
+	pub id: String,
+	// Client order id, empty when the order wasn't given one
+	pub order_link_id: String,
+	// Price and quantities are ticks and lots of this instrument
+	pub spec: InstrumentSpec,
+	pub side: Side,
+	pub price: Price,
+	// Original order quantity
+	pub qty: Qty,
+	// Quantity still working, qty - cum_filled_qty
+	pub leaves_qty: Qty,
+	pub cum_filled_qty: Qty,
+	// None until the first fill
+	pub avg_fill_price: Option<f64>,
+	pub state: OrderState,
+	pub position_idx: u8,
+	pub created_time: u64,
+	pub updated_time: u64,
+}
+
+impl Order {
+	// Starts out New with nothing filled
+	pub fn new(spec: &InstrumentSpec, id: String, side: Side, price: Price, qty: Qty, created_time: u64, updated_time: u64) -> Order {
+		Order {
+			id,
+			order_link_id: String::new(),
+			spec: spec.clone(),
+			side,
+			price,
+			qty,
+			leaves_qty: qty,
+			cum_filled_qty: Qty(0),
+			avg_fill_price: None,
+			state: OrderState::New,
+			position_idx: 0,
+			created_time,
+			updated_time
+		}
+	}
+	// 0 = one-way mode, 1 = hedge mode buy side, 2 = hedge mode sell side
+	pub fn with_position_idx(mut self, position_idx: u8) -> Order {
+		self.position_idx = position_idx;
+		self
 	}
 
-	#[derive(Debug, PartialEq)]
-	struct Oms {
-		sell_side_orders_active: OrderMap,
-		sell_side_orders_pending: OrderMap,
-		buy_side_orders_active: OrderMap,
-		buy_side_orders_pending: OrderMap
+	pub fn with_order_link_id(mut self, order_link_id: &str) -> Order {
+		self.order_link_id = order_link_id.to_string();
+		self
 	}
+	// Sets the starting state without validation e.g. when adopting an order found on the exchange
+	pub fn with_state(mut self, state: OrderState) -> Order {
+		self.state = state;
+		self
+	}
+	// Moves to the next state if the lifecycle allows it, otherwise the order is left untouched
+	pub fn transition(&mut self, next: OrderState) -> Result<(), OmsError> {
 
-	impl Oms {
+		if !self.state.can_transition_to(next) {
+			return Err(OmsError::InvalidTransition { id: self.id.clone(), from: self.state, to: next });
+		}
 
-		pub fn new() -> Oms {
-			Oms { 
-				sell_side_orders_active: RefCell::new(HashMap::new()),
-				sell_side_orders_pending: RefCell::new(HashMap::new()),
-				buy_side_orders_active: RefCell::new(HashMap::new()),
-				buy_side_orders_pending: RefCell::new(HashMap::new())
-			}
+		self.state = next;
+
+		Ok(())
+	}
+	// Books a fill, the order becomes PartiallyFilled or Filled. Fills racing a cancel keep
+	// the order in PendingCancel until it's fully filled
+	pub fn fill(&mut self, qty: Qty, price: f64) -> Result<(), OmsError> {
+
+		if !(self.state == OrderState::PendingNew || self.state.is_working()) {
+			return Err(OmsError::InvalidTransition { id: self.id.clone(), from: self.state, to: OrderState::PartiallyFilled });
 		}
-		
-		pub fn handle_mapping(&mut self, order_value: OrderStatus) -> AltReturn {
 
-			match order_value {
+		if qty <= Qty(0) || qty > self.leaves_qty {
+			return Err(OmsError::InvalidFill { id: self.id.clone(), qty, leaves_qty: self.leaves_qty });
+		}
 
-				OrderStatus::Active(order_position) => {
+		let filled = self.cum_filled_qty + qty;
+		let notional = self.avg_fill_price.unwrap_or(0.0) * self.spec.qty_value(self.cum_filled_qty) + price * self.spec.qty_value(qty);
 
-					match order_position {
-						OrderPosition::BuySide(order) => {
-							AltReturn::SomeOrder((&self.buy_side_orders_active, order))
-						}
+		self.avg_fill_price = Some(notional / self.spec.qty_value(filled));
+		self.cum_filled_qty = filled;
+		self.leaves_qty = self.leaves_qty - qty;
 
-						OrderPosition::SellSide(order) => {
-							AltReturn::SomeOrder((&self.sell_side_orders_active, order))
-						}
+		if self.leaves_qty == Qty(0) {
+			self.state = OrderState::Filled;
+		} else if self.state != OrderState::PendingCancel {
+			self.state = OrderState::PartiallyFilled;
+		}
 
-						OrderPosition::BuySideId(order_id) => {
-							AltReturn::SomeOrderId((&self.buy_side_orders_active, order_id))
-						}
+		Ok(())
+	}
 
-						OrderPosition::SellSideId(order_id) => {
-							AltReturn::SomeOrderId((&self.sell_side_orders_active, order_id))
-						}
-					}
-				}
+	pub fn symbol(&self) -> &str {
+		&self.spec.symbol
+	}
+	// Decimal values of the price and quantities, as the exchange shows them
+	pub fn price_value(&self) -> f64 {
+		self.spec.price_value(self.price)
+	}
+
+	pub fn qty_value(&self) -> f64 {
+		self.spec.qty_value(self.qty)
+	}
 
-				OrderStatus::Pending(order_position) => {
+	pub fn leaves_qty_value(&self) -> f64 {
+		self.spec.qty_value(self.leaves_qty)
+	}
 
-					match order_position {
-						OrderPosition::BuySide(order) => {
-							AltReturn::SomeOrder((&self.buy_side_orders_pending, order))
-						}
+	pub fn cum_filled_qty_value(&self) -> f64 {
+		self.spec.qty_value(self.cum_filled_qty)
+	}
+}
 
-						OrderPosition::SellSide(order) => {
-							AltReturn::SomeOrder((&self.sell_side_orders_pending, order))
-						}
+#[derive(Debug, PartialEq, Default)]
+pub struct Oms {
+	sell_side_orders_active: OrderMap,
+	sell_side_orders_pending: OrderMap,
+	buy_side_orders_active: OrderMap,
+	buy_side_orders_pending: OrderMap,
+	// Specs by symbol for orders adopted from the order stream, others get the fallback grid
+	instruments: HashMap<String, InstrumentSpec>,
+	// Every tracked order by id with where it's filed, so callers only need the id
+	ids: HashMap<String, (Side, OrderState)>,
+	// orderLinkId to order id, orders without a link id aren't in here
+	link_ids: HashMap<String, String>,
+	symbols: HashMap<String, BTreeSet<String>>,
+	states: HashMap<OrderState, BTreeSet<String>>,
+	// Ids by price in ticks for each symbol and side
+	prices: HashMap<(String, Side), BTreeMap<Price, BTreeSet<String>>>,
+	// Orders that reached a terminal state with that state and their last updated_time
+	closed: HashMap<String, (OrderState, u64)>
+}
+
+impl Oms {
+
+	pub fn new() -> Oms {
+		Oms {
+			sell_side_orders_active: RefCell::new(HashMap::new()),
+			sell_side_orders_pending: RefCell::new(HashMap::new()),
+			buy_side_orders_active: RefCell::new(HashMap::new()),
+			buy_side_orders_pending: RefCell::new(HashMap::new()),
+			instruments: HashMap::new(),
+			ids: HashMap::new(),
+			link_ids: HashMap::new(),
+			symbols: HashMap::new(),
+			states: HashMap::new(),
+			prices: HashMap::new(),
+			closed: HashMap::new()
+		}
+	}
+
+	pub fn with_instrument(mut self, spec: InstrumentSpec) -> Oms {
+		self.instruments.insert(spec.symbol.clone(), spec);
+		self
+	}
+
+	pub fn instrument(&self, symbol: &str) -> Option<&InstrumentSpec> {
+		self.instruments.get(symbol)
+	}
+
+	// Order maps are for reading, changes have to go through the oms to keep the indexes
+	// in step. Returns active buy side orders
+	pub fn buy_side_orders_active(&self) -> &OrderMap {
+		&self.buy_side_orders_active
+	}
+	// Returns pending buy side orders
+	pub fn buy_side_orders_pending(&self) -> &OrderMap {
+		&self.buy_side_orders_pending
+	}
+	// Returns active sell side orders
+	pub fn sell_side_orders_active(&self) -> &OrderMap {
+		&self.sell_side_orders_active
+	}
+	// Returns pending sell side orders
+	pub fn sell_side_orders_pending(&self) -> &OrderMap {
+		&self.sell_side_orders_pending
+	}
+	// The map an order in this state lives in, terminal orders aren't kept
+	fn map_for(&self, side: Side, state: OrderState) -> Option<&OrderMap> {
+
+		match (side, state.is_pending(), state.is_working()) {
+			(Side::Buy, true, _) => Some(&self.buy_side_orders_pending),
+			(Side::Buy, _, true) => Some(&self.buy_side_orders_active),
+			(Side::Sell, true, _) => Some(&self.sell_side_orders_pending),
+			(Side::Sell, _, true) => Some(&self.sell_side_orders_active),
+			_ => None
+		}
+	}
+	// Finds the map holding the order with this id
+	fn find(&self, id: &str) -> Option<&OrderMap> {
+
+		let (side, state) = self.ids.get(id)?;
+
+		self.map_for(*side, *state)
+	}
+
+	fn index(&mut self, order: &Order) {
+
+		self.ids.insert(order.id.clone(), (order.side, order.state));
+
+		if !order.order_link_id.is_empty() {
+			self.link_ids.insert(order.order_link_id.clone(), order.id.clone());
+		}
+
+		self.symbols
+			.entry(order.symbol().to_string())
+			.or_default()
+			.insert(order.id.clone());
+
+		self.states
+			.entry(order.state)
+			.or_default()
+			.insert(order.id.clone());
+
+		self.prices
+			.entry((order.symbol().to_string(), order.side))
+			.or_default()
+			.entry(order.price)
+			.or_default()
+			.insert(order.id.clone());
+	}
+
+	fn unindex(&mut self, order: &Order) {
+
+		self.ids.remove(&order.id);
+
+		if self.link_ids.get(&order.order_link_id) == Some(&order.id) {
+			self.link_ids.remove(&order.order_link_id);
+		}
+
+		if let Some(ids) = self.symbols.get_mut(order.symbol()) {
+			ids.remove(&order.id);
+
+			if ids.is_empty() {
+				self.symbols.remove(order.symbol());
+			}
+		}
+
+		if let Some(ids) = self.states.get_mut(&order.state) {
+			ids.remove(&order.id);
+		}
 
-						OrderPosition::BuySideId(order_id) => {
-							AltReturn::SomeOrderId((&self.buy_side_orders_pending, order_id))
-						}
+		let key = (order.symbol().to_string(), order.side);
 
-						OrderPosition::SellSideId(order_id) => {
-							AltReturn::SomeOrderId((&self.sell_side_orders_pending, order_id))
-						}
-					}
+		if let Some(prices) = self.prices.get_mut(&key) {
+			if let Some(ids) = prices.get_mut(&order.price) {
+				ids.remove(&order.id);
+
+				if ids.is_empty() {
+					prices.remove(&order.price);
 				}
 			}
+
+			if prices.is_empty() {
+				self.prices.remove(&key);
+			}
 		}
-		
-		pub fn add_order (&mut self, order_value: OrderStatus) {
+	}
+	// Removes the order from its map and every index
+	fn take(&mut self, id: &str) -> Option<Order> {
+
+		let order = self
+			.find(id)?
+			.borrow_mut()
+			.remove(id)?;
+
+		self.unindex(&order);
+
+		Some(order)
+	}
+	// Files the order under its side and state, replacing any order with the same id.
+	// Terminal orders leave a tombstone instead
+	fn store(&mut self, order: Order) {
+
+		self.take(&order.id);
 
-			let AltReturn::SomeOrder((map, order)) = self.handle_mapping(order_value) else { panic!() };
+		let Some(map) = self.map_for(order.side, order.state) else {
+			self.closed.insert(order.id, (order.state, order.updated_time));
+			return;
+		};
 
-			map
-				.borrow_mut()
-				.insert(order.id.clone(), order);
+		map
+			.borrow_mut()
+			.insert(order.id.clone(), order.clone());
+
+		self.closed.remove(&order.id);
+		self.index(&order);
+	}
+	// Tracks a new order under side and state, which have to match the order's own
+	pub fn add(&mut self, side: Side, state: OrderState, order: Order) -> Result<(), OmsError> {
+
+		if order.side != side || order.state != state {
+			return Err(OmsError::InvalidVariant { id: order.id, side, state });
 		}
 
-		pub fn delete_order(&mut self, order_value: OrderStatus) {
+		if state.is_terminal() {
+			return Err(OmsError::Closed { id: order.id, state });
+		}
 
-			let AltReturn::SomeOrderId((map, order_id)) = self.handle_mapping(order_value) else { panic!() };
+		let linked = !order.order_link_id.is_empty() && self.link_ids.contains_key(&order.order_link_id);
 
-			map 
-				.borrow_mut()
-				.remove(&order_id);
+		if linked || self.ids.contains_key(&order.id) {
+			return Err(OmsError::DuplicateOrder(order.id));
 		}
 
-		pub fn get_order(&mut self, order_value: OrderStatus) -> Order {
+		self.store(order);
+
+		Ok(())
+	}
+
+	pub fn get(&self, id: &str) -> Result<Order, OmsError> {
+
+		self.find(id)
+			.and_then(|map| map.borrow().get(id).cloned())
+			.ok_or_else(|| OmsError::UnknownOrder(id.to_string()))
+	}
+	// Looks an order up by its client order id
+	pub fn get_by_link_id(&self, order_link_id: &str) -> Result<Order, OmsError> {
+
+		let id = self
+			.link_ids
+			.get(order_link_id)
+			.ok_or_else(|| OmsError::UnknownOrder(order_link_id.to_string()))?;
+
+		self.get(id)
+	}
+	// Stops tracking the order and returns it
+	pub fn remove(&mut self, id: &str) -> Result<Order, OmsError> {
+
+		self.take(id)
+			.ok_or_else(|| OmsError::UnknownOrder(id.to_string()))
+	}
+	// Terminal state of an order that is no longer tracked, None for open or unknown orders
+	pub fn closed_state(&self, id: &str) -> Option<OrderState> {
+
+		self.closed
+			.get(id)
+			.map(|(state, _)| *state)
+	}
+	// Forgets orders closed before updated_time, updates for them will be treated as new orders
+	pub fn prune_closed(&mut self, updated_time: u64) {
+
+		self.closed.retain(|_, (_, closed_time)| *closed_time >= updated_time);
+	}
+
+	fn collect<'a>(&self, ids: impl IntoIterator<Item = &'a String>) -> Vec<Order> {
+
+		ids.into_iter()
+			.filter_map(|id| self.get(id).ok())
+			.collect()
+	}
+	// Orders for this symbol in id order
+	pub fn orders_by_symbol(&self, symbol: &str) -> Vec<Order> {
+
+		self.symbols
+			.get(symbol)
+			.map_or_else(Vec::new, |ids| self.collect(ids))
+	}
+	// Orders in this state in id order
+	pub fn orders_by_state(&self, state: OrderState) -> Vec<Order> {
+
+		self.states
+			.get(&state)
+			.map_or_else(Vec::new, |ids| self.collect(ids))
+	}
+	// Orders for one symbol and side priced within range, pending and working, in ascending price
+	pub fn orders_by_price<R: RangeBounds<Price>>(&self, symbol: &str, side: Side, range: R) -> Vec<Order> {
+
+		let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+
+		// BTreeMap::range panics on these, a reversed range or (x, x) exclusive holds nothing
+		let empty = match bounds {
+			(Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+			(Bound::Included(start) | Bound::Excluded(start), Bound::Included(end) | Bound::Excluded(end)) => start > end,
+			_ => false
+		};
+
+		let prices = match self.prices.get(&(symbol.to_string(), side)) {
+			Some(prices) if !empty => prices,
+			_ => return Vec::new()
+		};
+
+		prices
+			.range(bounds)
+			.flat_map(|(_, ids)| self.collect(ids))
+			.collect()
+	}
+	// Working orders for one symbol and side priced strictly above price e.g. active bids above X
+	pub fn working_above(&self, symbol: &str, side: Side, price: Price) -> Vec<Order> {
+
+		self.orders_by_price(symbol, side, (Bound::Excluded(price), Bound::Unbounded))
+			.into_iter()
+			.filter(|order| order.state.is_working())
+			.collect()
+	}
+	// Working orders for one symbol and side priced strictly below price
+	pub fn working_below(&self, symbol: &str, side: Side, price: Price) -> Vec<Order> {
+
+		self.orders_by_price(symbol, side, ..price)
+			.into_iter()
+			.filter(|order| order.state.is_working())
+			.collect()
+	}
+	// Applies a state change, orders that reach a terminal state stop being tracked.
+	// Returns the order as it is after the transition
+	pub fn transition(&mut self, id: &str, next: OrderState) -> Result<Order, OmsError> {
 
-			let AltReturn::SomeOrderId((map, order_id)) = self.handle_mapping(order_value) else { panic!() };
+		let mut order = self.remove(id)?;
+		let result = order.transition(next);
 
-			let binding_map = map.borrow();
+		self.store(order.clone());
+		result.map(|_| order)
+	}
+	// Books a fill against a tracked order, see Order::fill
+	pub fn apply_fill(&mut self, id: &str, qty: Qty, price: f64) -> Result<Order, OmsError> {
+
+		let mut order = self.remove(id)?;
+		let result = order.fill(qty, price);
+
+		self.store(order.clone());
+		result.map(|_| order)
+	}
+	// Moves an order through its lifecycle from the exchange's view of it. Quantities come
+	// straight from the update, which is a full snapshot of the order
+	pub fn apply_order_update(&mut self, update: &OrderUpdate) -> Result<(), OmsError> {
 
-			let anw = binding_map
-				.get(&order_id)
-				.expect("Failed to get order");
+		let mut next: OrderState = update.order_status.into();
+
+		// Updates older than the one that closed an order are stale and must not bring it back.
+		// Anything newer that would reopen it is an error
+		if let Some(&(state, closed_time)) = self.closed.get(&update.order_id) {
+			if update.updated_time <= closed_time || next.is_terminal() {
+				return Ok(());
+			}
+
+			return Err(OmsError::Closed { id: update.order_id.clone(), state });
+		}
+
+		// Orders tracked before the exchange gave them an id are found by their client order id
+		let tracked = self
+			.get(&update.order_id)
+			.or_else(|_| self.get_by_link_id(&update.order_link_id));
+
+		let mut order = match tracked {
+			Ok(order) => order,
+
+			// Orders placed outside this oms e.g. from the web UI are adopted as they are
+			Err(_) if next.is_terminal() => return Ok(()),
+			Err(_) => {
+				let spec = self
+					.instrument(&update.symbol)
+					.cloned()
+					.unwrap_or_else(|| InstrumentSpec::fallback(&update.symbol));
+
+				Order::new(
+					&spec,
+					update.order_id.clone(),
+					update.side,
+					to_price(&spec, &update.order_id, update.price)?,
+					to_qty(&spec, &update.order_id, update.qty)?,
+					update.created_time,
+					update.updated_time
+				)
+					.with_position_idx(update.position_idx)
+					.with_order_link_id(&update.order_link_id)
+					.with_state(OrderState::PendingNew)
+			}
+		};
+
+		// Updates can arrive out of order, never let an older one overwrite a newer one
+		if order.updated_time > update.updated_time {
+			return Ok(());
+		}
 
-			anw.clone()
+		// The stream can confirm an order before the caller has marked it sent, it was sent
+		if order.state == OrderState::New {
+			order.transition(OrderState::PendingNew)?;
+		}
 
+		// A fill while a cancel is in flight doesn't mean the cancel failed
+		if order.state == OrderState::PendingCancel && matches!(next, OrderState::Active | OrderState::PartiallyFilled) {
+			next = OrderState::PendingCancel;
 		}
-		// Will return current inventory delta
-		pub fn get_inventory_delta(&self) -> f64 {
 
-			let bid_delta: f64 = self.buy_side_orders_active
-				.borrow()
-				.values()
-				.map(|order| order.qty)
-				.sum();
+		let spec = &order.spec;
 
-			let ask_delta: f64 = self.sell_side_orders_active
-				.borrow()
-				.values()
-				.map(|order| order.qty)
-				.sum();
+		let price = to_price(spec, &update.order_id, update.price)?;
+		let qty = to_qty(spec, &update.order_id, update.qty)?;
+		let leaves_qty = to_qty(spec, &update.order_id, update.leaves_qty)?;
+		let cum_filled_qty = to_qty(spec, &update.order_id, update.cum_exec_qty)?;
 
-			let value = bid_delta - ask_delta;
+		order.transition(next)?;
 
-			value
+		if order.id != update.order_id {
+			self.take(&order.id);
+			order.id = update.order_id.clone();
 		}
 
-		pub fn get_size_to_target(&self, target_delta: f64) -> f64 {
-			
-			let current_delta = self
-				.get_inventory_delta();
+		order.price = price;
+		order.qty = qty;
+		order.leaves_qty = leaves_qty;
+		order.cum_filled_qty = cum_filled_qty;
+		order.order_link_id = update.order_link_id.clone();
+		order.avg_fill_price = update.avg_price.or(order.avg_fill_price);
+		order.updated_time = update.updated_time;
+
+		self.store(order);
+
+		Ok(())
+	}
+	// Applies order events from the private stream, other events are ignored. Every update is
+	// applied even if an earlier one fails, the first error is returned
+	pub fn apply_private_event(&mut self, event: &PrivateEvent) -> Result<(), OmsError> {
+
+		let mut result = Ok(());
 
-			let anw = (current_delta - target_delta).abs();
+		if let PrivateEvent::Orders(updates) = event {
+			for update in updates {
+				let applied = self.apply_order_update(update);
 
-			anw
+				if result.is_ok() {
+					result = applied;
+				}
+			}
 		}
+
+		result
+	}
+	// Will return current open order delta, the quantity still working on each side.
+	// This is order exposure not inventory, positions are kept by position::PositionKeeper
+	pub fn get_inventory_delta(&self) -> f64 {
+
+		let bid_delta: f64 = self.buy_side_orders_active
+			.borrow()
+			.values()
+			.map(|order| order.leaves_qty_value())
+			.sum();
+
+		let ask_delta: f64 = self.sell_side_orders_active
+			.borrow()
+			.values()
+			.map(|order| order.leaves_qty_value())
+			.sum();
+
+		bid_delta - ask_delta
 	}
+
+	pub fn get_size_to_target(&self, target_delta: f64) -> f64 {
+
+		let current_delta = self
+			.get_inventory_delta();
+
+		(current_delta - target_delta).abs()
+	}
+}
+
+fn to_price(spec: &InstrumentSpec, id: &str, value: f64) -> Result<Price, OmsError> {
+	spec.price(value, Rounding::Nearest)
+		.ok_or(OmsError::OutOfRange { id: id.to_string(), value })
+}
+
+fn to_qty(spec: &InstrumentSpec, id: &str, value: f64) -> Result<Qty, OmsError> {
+	spec.qty(value, Rounding::Nearest)
+		.ok_or(OmsError::OutOfRange { id: id.to_string(), value })
 }
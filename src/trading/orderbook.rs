@@ -1,248 +1,673 @@
-use std::cmp::Ordering;
+use std::fmt;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
-use ordered_float::OrderedFloat;
+use crate::trading::instrument::{ InstrumentSpec, Price, Rounding };
+use crate::trading::market::{ Level, Side };
+
+pub type Levels = BTreeMap<Price, RestingOrder>;
+pub type BidsMap = RefCell<Levels>;
+pub type AsksMap = RefCell<Levels>;
+
+// Sizes at or below this are treated as zero so float residue can't keep a level alive
+const SIZE_EPSILON: f64 = 1e-12;
+
+// Skew saturates here when a side has no depth, e^10 is ~22000x more depth on one side
+pub const MAX_SKEW: f64 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookSide {
+    Bids,
+    Asks
+}
+
+// How far from the touch a signal looks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Depth {
+    Levels(usize), // The best n levels of each side
+    Bps(f64) // Levels within n basis points of mid
+}
+
+// Distance from mid a depth profile point reaches out to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distance {
+    Absolute(f64), // In price units
+    Bps(f64) // In basis points of mid
+}
+
+// Cumulative resting size within one distance of mid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthPoint {
+    pub distance: Distance,
+    // The prices the distance reaches on each side
+    pub bid_price: f64,
+    pub ask_price: f64,
+    pub bid_depth: f64,
+    pub ask_depth: f64
+}
+
+impl DepthPoint {
+    // ln(bid depth) - ln(ask depth) within this distance, saturating like get_ordebook_skew
+    pub fn skew(&self) -> f64 {
+        skew(self.bid_depth, self.ask_depth)
+    }
+}
+
+// Liquidity vs distance from mid, one point per requested distance in the order given
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthProfile {
+    pub mid_price: f64,
+    pub points: Vec<DepthPoint>
+}
+
+#[derive(Clone)]
+pub enum RestingOrderType {
+    BidOrder(RestingOrder),
+    AskOrder(RestingOrder),
+    BidPrice(f64),
+    AskPrice(f64)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestingOrder {
+    pub price: f64,
+    pub size: f64,
+    pub ts: u128
+}
+
+impl RestingOrder {
+
+    pub fn new(price: f64, size: f64, ts: u128) -> RestingOrder {
+        RestingOrder { price, size, ts }
+    }
+}
+
+impl RestingOrderType {
+    // The side and price level an order or price refers to
+    pub fn level(&self) -> (BookSide, f64) {
+        match self {
+            RestingOrderType::BidOrder(order) => (BookSide::Bids, order.price),
+            RestingOrderType::AskOrder(order) => (BookSide::Asks, order.price),
+            RestingOrderType::BidPrice(price) => (BookSide::Bids, *price),
+            RestingOrderType::AskPrice(price) => (BookSide::Asks, *price)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderbookError {
+    NoSnapshot, // A delta arrived before the first snapshot or after a gap
+    Gap { expected: u64, received: u64 }, // Update ids are not contiguous, resync from a new snapshot
+    EmptySide(BookSide), // The query needs a level on a side that has none
+    MissingLevel { side: BookSide, price: f64 }, // No level rests at the requested price
+    NotAnOrder { side: BookSide, price: f64 }, // A bare price has no size to insert, see remove_level
+    InvalidSize(f64), // Sizes must be positive and fit the lot grid
+    InvalidPrice(f64), // Prices must be finite and fit the tick grid
+    InvalidDepth(Depth), // Depth must cover at least one level or a positive distance
+    InvalidDecay(f64), // Decay must be finite and can't be negative
+    InvalidDistance(Distance) // Distances can't be negative
+}
+
+impl fmt::Display for OrderbookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderbookError::NoSnapshot => write!(f, "delta received without a snapshot"),
+            OrderbookError::Gap { expected, received } => write!(f, "update id gap: expected {}, received {}", expected, received),
+            OrderbookError::EmptySide(side) => write!(f, "no {:?} in the book", side),
+            OrderbookError::MissingLevel { side, price } => write!(f, "no {:?} level at {}", side, price),
+            OrderbookError::NotAnOrder { side, price } => write!(f, "{:?} price {} has no size to insert", side, price),
+            OrderbookError::InvalidSize(size) => write!(f, "invalid size {}", size),
+            OrderbookError::InvalidPrice(price) => write!(f, "invalid price {}", price),
+            OrderbookError::InvalidDepth(depth) => write!(f, "invalid depth {:?}", depth),
+            OrderbookError::InvalidDecay(decay) => write!(f, "invalid decay {}", decay),
+            OrderbookError::InvalidDistance(distance) => write!(f, "invalid distance {:?}", distance)
+        }
+    }
+}
+
+impl std::error::Error for OrderbookError {}
+
+// Result of walking the book to fill a taker order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FillCost {
+    pub filled: f64,
+    // Volume weighted average price of the filled part
+    pub avg_price: f64,
+    // Price of the last level touched
+    pub worst_price: f64,
+    pub notional: f64,
+    pub levels: usize,
+    // Cost of the average price against mid, positive is worse for the taker. None when
+    // the other side is empty and there is no mid
+    pub slippage_bps: Option<f64>,
+    // Size the book was too thin to fill
+    pub remainder: f64
+}
+
+impl FillCost {
+    pub fn is_complete(&self) -> bool {
+        self.remainder <= SIZE_EPSILON
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Orderbook {
+    asks: AsksMap,
+    bids: BidsMap,
+    last_update_time: u128,
+    update_id: Option<u64>,
+    seq: u64,
+    spec: Option<InstrumentSpec>,
+    // Levels are keyed by ticks of the spec, or of the fallback grid until one is set
+    grid: InstrumentSpec
+}
+
+impl Default for Orderbook {
+    fn default() -> Orderbook {
+        Orderbook::new()
+    }
+}
+
+impl Orderbook {
+    pub fn new() -> Orderbook {
+        Orderbook {
+            asks: RefCell::new(BTreeMap::new()),
+            bids: RefCell::new(BTreeMap::new()),
+            last_update_time: 0,
+            update_id: None,
+            seq: 0,
+            spec: None,
+            grid: InstrumentSpec::fallback("")
+        }
+    }
+    // Keys levels by whole ticks, so the same price always lands on the same level however
+    // it was computed
+    pub fn with_spec(mut self, spec: InstrumentSpec) -> Orderbook {
+        self.grid = spec.clone();
+        self.spec = Some(spec);
+
+        let last_update_time = self.last_update_time;
+        let bids = self.clear_side(BookSide::Bids);
+        let asks = self.clear_side(BookSide::Asks);
+
+        for bid in bids.into_values() {
+            let _ = self.insert_order(RestingOrderType::BidOrder(bid));
+        }
 
-pub mod trading {
+        for ask in asks.into_values() {
+            let _ = self.insert_order(RestingOrderType::AskOrder(ask));
+        }
 
-    type BidsMap = RefCell<BTreeMap<OrderedFloat<f64>, RestingOrder>>;
-    type AsksMap = RefCell<BTreeMap<OrderedFloat<f64>, RestingOrder>>;
+        self.last_update_time = last_update_time;
+        self
+    }
 
-    #[derive(Clone)]
-    enum RestingOrderType {
-        BidOrder(RestingOrder),
-        AskOrder(RestingOrder),
-        BidPrice(f64),
-        AskPrice(f64)
+    pub fn spec(&self) -> Option<&InstrumentSpec> {
+        self.spec.as_ref()
     }
 
-    #[derive(Clone, Debug, PartialEq)]
-    struct RestingOrder {
-        price: f64,
-        size: f64,
-        ts: u128
+    fn price_key(&self, price: f64) -> Result<Price, OrderbookError> {
+        self.grid
+            .price(price, Rounding::Nearest)
+            .ok_or(OrderbookError::InvalidPrice(price))
+    }
+    // Band edges past the end of the grid reach every level on that side
+    fn band_edge(&self, price: f64, rounding: Rounding) -> Price {
+        self.grid
+            .price(price, rounding)
+            .unwrap_or(if price < 0.0 { Price(i64::MIN) } else { Price(i64::MAX) })
     }
 
-    #[derive(Clone, Debug, PartialEq)]
-    struct Orderbook {
-        asks: AsksMap,
-        bids: BidsMap,
-        last_update_time: u128,
+    fn on_grid(&self, mut order: RestingOrder) -> Result<RestingOrder, OrderbookError> {
+        let size = order.size;
+
+        order.price = self.grid.price_value(self.price_key(order.price)?);
+        order.size = self.grid
+            .round_qty(size, Rounding::Nearest)
+            .ok_or(OrderbookError::InvalidSize(size))?;
+
+        Ok(order)
     }
+    // Replaces the whole book, Bybit also resends a snapshot (u = 1) after a service restart
+    pub fn apply_snapshot(&mut self, bids: &[Level], asks: &[Level], update_id: u64, seq: u64, ts: u128) {
+        self.clear_side(BookSide::Bids);
+        self.clear_side(BookSide::Asks);
 
-    impl Orderbook {
-        fn new() -> Orderbook {
+        self.apply_levels(bids, asks, ts);
 
-            Orderbook {
-                asks: RefCell::new(BTreeMap::new()),
-                bids: RefCell::new(BTreeMap::new()),
-                last_update_time: 0
-            }
+        self.update_id = Some(update_id);
+        self.seq = seq;
+        self.last_update_time = ts;
+    }
+    // Applies a delta where a size of 0 removes the level. update_id must follow on from the
+    // previous one, on a gap the book is left as is and rejects deltas until the next snapshot
+    pub fn apply_delta(&mut self, bids: &[Level], asks: &[Level], update_id: u64, seq: u64, ts: u128) -> Result<(), OrderbookError> {
+        let expected = self.update_id
+            .ok_or(OrderbookError::NoSnapshot)? + 1;
+
+        if update_id != expected {
+            self.update_id = None;
+            return Err(OrderbookError::Gap { expected, received: update_id });
         }
 
-        // Inserts resting order into orderbook 
-        fn insert_order (&mut self, order: RestingOrderType) {
+        self.apply_levels(bids, asks, ts);
 
-            match order {
-                RestingOrderType::BidOrder(bid) => {
-                    let price = OrderedFloat(bid.price);
-                    self.last_update_time = bid.ts; 
+        self.update_id = Some(update_id);
+        self.seq = seq;
+        self.last_update_time = ts;
 
-                    self.bids
-                        .borrow_mut()
-                        .insert(price, bid);
-                }
-
-                RestingOrderType::AskOrder(ask) => {
-                    let price = OrderedFloat(ask.price);
-                    self.last_update_time = ask.ts; 
+        Ok(())
+    }
 
-                    self.asks
-                        .borrow_mut()
-                        .insert(price, ask);
-                }
+    fn apply_levels(&mut self, bids: &[Level], asks: &[Level], ts: u128) {
+        for bid in bids {
+            if bid.size == 0.0 {
+                self.remove_level(RestingOrderType::BidPrice(bid.price));
+            } else {
+                let _ = self.insert_order(RestingOrderType::BidOrder(RestingOrder::new(bid.price, bid.size, ts)));
+            }
+        }
 
-                _ => todo!()
+        for ask in asks {
+            if ask.size == 0.0 {
+                self.remove_level(RestingOrderType::AskPrice(ask.price));
+            } else {
+                let _ = self.insert_order(RestingOrderType::AskOrder(RestingOrder::new(ask.price, ask.size, ts)));
             }
         }
-        // Returns ask closest to mid-price
-        fn get_ask(&self) -> RestingOrder {
+    }
+
+    // Inserts resting order into orderbook, returns the level it replaced.
+    // A bare price carries no size and is rejected, levels are deleted with remove_level.
+    // A size that rounds to zero lots deletes the level instead of leaving an empty one.
+    // Prices and sizes that aren't finite or don't fit the grid are rejected
+    pub fn insert_order (&mut self, order: RestingOrderType) -> Result<Option<RestingOrder>, OrderbookError> {
 
-            let binding = self.asks
-                .borrow();
+        let (side, order) = match order {
+            RestingOrderType::BidOrder(bid) => (BookSide::Bids, bid),
+            RestingOrderType::AskOrder(ask) => (BookSide::Asks, ask),
 
-            let (_key, value) = binding
-                .first_key_value()
-                .expect("Failed to get first key value");
+            price => {
+                let (side, price) = price.level();
+                return Err(OrderbookError::NotAnOrder { side, price });
+            }
+        };
 
-            RestingOrder { price: value.price, size: value.size,  ts: value.ts }
+        if order.size.is_nan() || order.size < 0.0 {
+            return Err(OrderbookError::InvalidSize(order.size));
         }
-        // Returns bid closest to mid-price
-        fn get_bid(&self) -> RestingOrder {
 
-            let binding = self.bids
-                .borrow();
+        let order = self.on_grid(order)?;
+        let price = self.price_key(order.price)?;
+        self.last_update_time = order.ts;
 
-            let (_key, value) = binding
-                .last_key_value()
-                .expect("Failed to get last key value");
+        let mut levels = self.side(side)
+            .borrow_mut();
 
-            RestingOrder { price: value.price, size: value.size, ts: value.ts }
+        if order.size <= SIZE_EPSILON {
+            return Ok(levels.remove(&price));
         }
-        // Returns all asks
-        fn get_asks(&self) -> &BidsMap {
-            let asks = &self.asks;
 
-            asks
+        Ok(levels.insert(price, order))
+    }
+    // Deletes the level at the order's price, returns the removed level. Nothing rests at a
+    // price off the grid's range
+    pub fn remove_level(&mut self, price: RestingOrderType) -> Option<RestingOrder> {
+        let (side, price) = price.level();
+        let price = self.price_key(price).ok()?;
+
+        self.side(side)
+            .borrow_mut()
+            .remove(&price)
+    }
+    // Takes size off the level at the order's price, removing it once nothing is left.
+    // Returns the level as it was before the reduction, None if no level rests there
+    pub fn reduce_size(&mut self, price: RestingOrderType, size: f64) -> Result<Option<RestingOrder>, OrderbookError> {
+        if size.is_nan() || size <= 0.0 {
+            return Err(OrderbookError::InvalidSize(size));
         }
-        // Returns all bids
-        fn get_bids(&self) -> &AsksMap {
-            let bids = &self.bids;
 
-            bids
-        }
-        // Calculates the current orderbook skew
-        fn get_ordebook_skew(&self) -> f64 {
+        let (side, price) = price.level();
+        let price = self.price_key(price)?;
 
-            let buy_side_depth: f64 = self
-                .bids
-                .borrow()
-                .values()
-                .map(|order| order.size)
-                .sum();
+        let mut levels = self.side(side)
+            .borrow_mut();
 
-            let sell_side_depth: f64 = self
-                .asks
-                .borrow()
-                .values()
-                .map(|order| order.size)
-                .sum();
+        let Some(level) = levels.get_mut(&price) else {
+            return Ok(None);
+        };
 
-            let value = buy_side_depth.ln() - sell_side_depth.ln();
+        let previous = level.clone();
 
-            value
-        }
-        // Calculates the current orderbook skew by range
-        fn get_ordebook_skew_by_range(&self, diff: &f64) -> f64 {
-            let mid_price = self.get_mid_price();
+        // Reducing by more than the grid can hold leaves nothing either
+        level.size = self.grid
+            .round_qty(level.size - size, Rounding::Nearest)
+            .unwrap_or(0.0);
 
-            let lower_bound = OrderedFloat(mid_price - diff);
-            let upper_bound = OrderedFloat(mid_price + diff);
+        if level.size <= SIZE_EPSILON {
+            levels.remove(&price);
+        }
 
-            let a_binding = self
-                .asks
-                .borrow();
+        Ok(Some(previous))
+    }
+    // Empties one side of the book, returns the levels that were on it
+    pub fn clear_side(&mut self, side: BookSide) -> Levels {
+        std::mem::take(&mut *self.side(side).borrow_mut())
+    }
 
-            let b_binding = self
-                .bids
-                .borrow();
+    fn side(&self, side: BookSide) -> &RefCell<Levels> {
+        match side {
+            BookSide::Bids => &self.bids,
+            BookSide::Asks => &self.asks
+        }
+    }
+    // Returns ask closest to mid-price, None when there are no asks
+    pub fn get_ask(&self) -> Option<RestingOrder> {
+        self.asks
+            .borrow()
+            .first_key_value()
+            .map(|(_key, value)| value.clone())
+    }
+    // Returns bid closest to mid-price, None when there are no bids
+    pub fn get_bid(&self) -> Option<RestingOrder> {
+        self.bids
+            .borrow()
+            .last_key_value()
+            .map(|(_key, value)| value.clone())
+    }
+    // Returns all asks
+    pub fn get_asks(&self) -> &AsksMap {
+        &self.asks
+    }
+    // Returns all bids
+    pub fn get_bids(&self) -> &BidsMap {
+        &self.bids
+    }
+    // Returns the timestamp of the last inserted order
+    pub fn last_update_time(&self) -> u128 {
+        self.last_update_time
+    }
+    // Update id of the last applied snapshot or delta, None until synced or after a gap
+    pub fn update_id(&self) -> Option<u64> {
+        self.update_id
+    }
+    // Cross sequence of the last applied update, comparable across depths of the same symbol
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+    // Calculates the current orderbook skew, ln(bid depth) - ln(ask depth).
+    // An empty book is balanced (0) and a one sided book saturates at +/- MAX_SKEW
+    pub fn get_ordebook_skew(&self) -> f64 {
+
+        let buy_side_depth: f64 = self
+            .bids
+            .borrow()
+            .values()
+            .map(|order| order.size)
+            .sum();
+
+        let sell_side_depth: f64 = self
+            .asks
+            .borrow()
+            .values()
+            .map(|order| order.size)
+            .sum();
+
+        skew(buy_side_depth, sell_side_depth)
+    }
+    // Calculates the orderbook skew using only the levels within diff of mid
+    pub fn get_ordebook_skew_by_range(&self, diff: &f64) -> Result<f64, OrderbookError> {
+        let profile = self.get_depth_profile(&[Distance::Absolute(*diff)])?;
 
-            let a_value = a_binding
-                .range(..upper_bound);
+        Ok(profile.points[0].skew())
+    }
+    // Cumulative bid depth in [mid - distance, mid] and ask depth in [mid, mid + distance] for
+    // each distance. Levels on the wrong side of mid in a crossed book are never counted
+    pub fn get_depth_profile(&self, distances: &[Distance]) -> Result<DepthProfile, OrderbookError> {
+        let mid_price = self.get_mid_price()?;
+
+        let bids = self.bids.borrow();
+        let asks = self.asks.borrow();
+
+        let points = distances
+            .iter()
+            .map(|&distance| {
+                let offset = match distance {
+                    Distance::Absolute(offset) => offset,
+                    Distance::Bps(bps) => mid_price * bps / 10_000.0
+                };
+
+                if offset.is_nan() || offset < 0.0 {
+                    return Err(OrderbookError::InvalidDistance(distance));
+                }
 
-            let b_value = b_binding
-                .range(lower_bound..);
+                let bid_price = mid_price - offset;
+                let ask_price = mid_price + offset;
 
-            let ask_side_depth_range: f64 = a_value
-                .map(|order| order.1.size)
-                .sum();
+                // Only whole ticks inside each band, a mid between ticks belongs to neither side
+                let bid_depth = depth_between(&bids, self.band_edge(bid_price, Rounding::Up), self.band_edge(mid_price, Rounding::Down));
+                let ask_depth = depth_between(&asks, self.band_edge(mid_price, Rounding::Up), self.band_edge(ask_price, Rounding::Down));
 
-            println!("{:?}", ask_side_depth_range);
+                Ok(DepthPoint { distance, bid_price, ask_price, bid_depth, ask_depth })
+            })
+            .collect::<Result<Vec<DepthPoint>, OrderbookError>>()?;
 
-            let buy_side_depth_range: f64 = b_value
-                .map(|order| order.1.size)
-                .sum();
+        Ok(DepthProfile { mid_price, points })
+    }
+    // Gets orderbook mid price
+    pub fn get_mid_price(&self) -> Result<f64, OrderbookError> {
+        let (bid, ask) = self.best_prices()?;
 
-            println!("{:?}", buy_side_depth_range);
+        Ok((bid + ask) / 2.0)
+    }
+    // Size weighted top of book, leans towards the side with less size resting
+    pub fn get_microprice(&self) -> Result<f64, OrderbookError> {
+        self.get_weighted_mid(1)
+    }
+    // Microprice over the best n levels, each side's VWAP weighted by the opposite side's depth
+    pub fn get_weighted_mid(&self, levels: usize) -> Result<f64, OrderbookError> {
+        let mid_price = self.get_mid_price()?;
+        let depth = Depth::Levels(levels);
 
-            let value = buy_side_depth_range.ln() - ask_side_depth_range.ln();
+        let (bid_price, bid_size) = vwap(&self.levels_within(BookSide::Bids, depth, mid_price)?);
+        let (ask_price, ask_size) = vwap(&self.levels_within(BookSide::Asks, depth, mid_price)?);
 
-            value
-            
+        Ok((bid_price * ask_size + ask_price * bid_size) / (bid_size + ask_size))
+    }
+    // (bid depth - ask depth) / (bid depth + ask depth), from -1 (all asks) to 1 (all bids)
+    pub fn get_imbalance(&self, depth: Depth) -> Result<f64, OrderbookError> {
+        let mid_price = self.get_mid_price()?;
+
+        let bid_depth: f64 = self
+            .levels_within(BookSide::Bids, depth, mid_price)?
+            .iter()
+            .map(|order| order.size)
+            .sum();
+
+        let ask_depth: f64 = self
+            .levels_within(BookSide::Asks, depth, mid_price)?
+            .iter()
+            .map(|order| order.size)
+            .sum();
+
+        Ok(imbalance(bid_depth, ask_depth))
+    }
+    // Imbalance over the whole book with each level weighted by exp(-decay * bps from mid),
+    // so size near the touch counts most. decay = 0 weighs every level equally
+    pub fn get_decay_imbalance(&self, decay: f64) -> Result<f64, OrderbookError> {
+        if !decay.is_finite() || decay < 0.0 {
+            return Err(OrderbookError::InvalidDecay(decay));
         }
-        // Gets orderbook mid price
-        fn get_mid_price(&self) -> f64 {
-            let bid_price = self
-                .get_bid()
-                .price;
 
-            let ask_price = self
-                .get_ask()
-                .price;
+        let mid_price = self.get_mid_price()?;
 
-            let mid_price = (bid_price + ask_price) / 2.0;
+        let weighted = |levels: &Levels| -> f64 {
+            levels
+                .values()
+                .map(|order| {
+                    let distance_bps = (order.price - mid_price).abs() / mid_price * 10_000.0;
+                    order.size * (-decay * distance_bps).exp()
+                })
+                .sum()
+        };
 
-            mid_price
-        }
-        // Gets orderbook spread
-        fn get_orderbook_spread(&self) -> f64 {
-            let bid_price = self
-                .get_bid()
-                .price;
+        let bid_depth = weighted(&self.bids.borrow());
+        let ask_depth = weighted(&self.asks.borrow());
 
-            let ask_price = self
-                .get_ask()
-                .price;
+        Ok(imbalance(bid_depth, ask_depth))
+    }
+    // Levels of one side from the touch outwards, limited by depth
+    fn levels_within(&self, side: BookSide, depth: Depth, mid_price: f64) -> Result<Vec<RestingOrder>, OrderbookError> {
+        let binding = self.side(side).borrow();
+
+        let book: Box<dyn Iterator<Item = &RestingOrder>> = match side {
+            BookSide::Bids => Box::new(binding.values().rev()),
+            BookSide::Asks => Box::new(binding.values())
+        };
+
+        let levels = match depth {
+            Depth::Levels(0) => return Err(OrderbookError::InvalidDepth(depth)),
+            Depth::Levels(n) => book.take(n).cloned().collect(),
+
+            Depth::Bps(bps) if bps.is_nan() || bps <= 0.0 => return Err(OrderbookError::InvalidDepth(depth)),
+            Depth::Bps(bps) => {
+                let distance = mid_price * bps / 10_000.0;
+
+                book
+                    .take_while(|order| (order.price - mid_price).abs() <= distance)
+                    .cloned()
+                    .collect()
+            }
+        };
 
-            let spread = ask_price - bid_price;
+        Ok(levels)
+    }
+    // Gets orderbook spread
+    pub fn get_orderbook_spread(&self) -> Result<f64, OrderbookError> {
+        let (bid, ask) = self.best_prices()?;
+
+        Ok(ask - bid)
+    }
 
-            spread
+    fn best_prices(&self) -> Result<(f64, f64), OrderbookError> {
+        let bid = self
+            .get_bid()
+            .ok_or(OrderbookError::EmptySide(BookSide::Bids))?;
+
+        let ask = self
+            .get_ask()
+            .ok_or(OrderbookError::EmptySide(BookSide::Asks))?;
+
+        Ok((bid.price, ask.price))
+    }
+    // Checks to see if your trade size can be filled in full at a specific price
+    // Ok(true) = Trade is safe, the level holds at least size
+    // Ok(false) = Trade is unsafe
+    // Only looks at one level, use cost_to_fill for orders that would walk the book
+    pub fn safety_check_size (&self, price: RestingOrderType, size: f64) -> Result<bool, OrderbookError> {
+        let (side, price) = price.level();
+
+        let check_size = self
+            .side(side)
+            .borrow()
+            .get(&self.price_key(price)?)
+            .ok_or(OrderbookError::MissingLevel { side, price })?
+            .size;
+
+        Ok(size <= check_size)
+    }
+    // Walks the opposite side from best price outwards as a taker of size would, buys
+    // consume asks and sells consume bids. Anything the book can't absorb is the remainder.
+    // Only the side being taken has to have levels
+    pub fn cost_to_fill(&self, side: Side, size: f64) -> Result<FillCost, OrderbookError> {
+        if size.is_nan() || size <= 0.0 {
+            return Err(OrderbookError::InvalidSize(size));
         }
-        // Checks to see if your trade size can be filled in full at a specific price
-        // ToDo: Determine what should happen at equal
-        // true = Trade is safe 
-        // false = Trade is unsafe
-        fn safety_check_size (&self, price: RestingOrderType, size: f64) -> bool {
 
-            match price {
-                RestingOrderType::BidPrice(bid) => {
-                    let check_price = OrderedFloat(bid);
-                    let check_size = self.bids
-                        .borrow()
-                        .get(&check_price)
-                        .expect("Failed to get bid size")
-                        .size;
+        let (book_side, best) = match side {
+            Side::Buy => (BookSide::Asks, self.get_ask()),
+            Side::Sell => (BookSide::Bids, self.get_bid())
+        };
 
-                    let result = size.partial_cmp(&check_size)
-                        .expect("Failed to compare values");
+        let best = best.ok_or(OrderbookError::EmptySide(book_side))?;
+        let mid_price = self.get_mid_price().ok();
 
-                    match result {
-                        Ordering::Greater => false,
-                        Ordering::Equal => true,
-                        Ordering::Less => true
-                    }
-                }
+        let binding = match side {
+            Side::Buy => self.asks.borrow(),
+            Side::Sell => self.bids.borrow()
+        };
 
-                RestingOrderType::AskPrice(ask) => {
-                    let check_price = OrderedFloat(ask);
-                    let check_size = self.asks 
-                        .borrow()
-                        .get(&check_price)
-                        .expect("Failed to get ask size")
-                        .size;
-
-                    let result = size.partial_cmp(&check_size)
-                        .expect("Failed to compare values");
-
-                    match result {
-                        Ordering::Greater => false,
-                        Ordering::Equal => true,
-                        Ordering::Less => true
-                    }
-                }
+        let book: Box<dyn Iterator<Item = &RestingOrder>> = match side {
+            Side::Buy => Box::new(binding.values()),
+            Side::Sell => Box::new(binding.values().rev())
+        };
 
-                _ => todo!()
-            }
-        }
-        // Safety Check
-        fn safety_check_spread(&self, max_spread: f64) -> bool {
-            let current_spread = self.get_orderbook_spread();
+        let mut remainder = size;
+        let mut notional = 0.0;
+        let mut worst_price = best.price;
+        let mut levels = 0;
 
-            if current_spread > max_spread {
-                false
-            } else {
-                true
+        for level in book {
+            if remainder <= SIZE_EPSILON {
+                break;
             }
+
+            let take = remainder.min(level.size);
+
+            notional += take * level.price;
+            remainder -= take;
+            worst_price = level.price;
+            levels += 1;
         }
+
+        let remainder = if remainder <= SIZE_EPSILON { 0.0 } else { remainder };
+        let filled = size - remainder;
+        let avg_price = notional / filled;
+
+        let slippage_bps = mid_price.map(|mid_price| match side {
+            Side::Buy => (avg_price - mid_price) / mid_price * 10_000.0,
+            Side::Sell => (mid_price - avg_price) / mid_price * 10_000.0
+        });
+
+        Ok(FillCost { filled, avg_price, worst_price, notional, levels, slippage_bps, remainder })
+    }
+    // Safety Check
+    pub fn safety_check_spread(&self, max_spread: f64) -> Result<bool, OrderbookError> {
+        let current_spread = self.get_orderbook_spread()?;
+
+        Ok(current_spread <= max_spread)
+    }
+}
+
+// Total size resting from low to high inclusive, nothing when the band holds no tick
+fn depth_between(levels: &Levels, low: Price, high: Price) -> f64 {
+    if low > high {
+        return 0.0;
+    }
+
+    levels
+        .range(low..=high)
+        .map(|(_price, order)| order.size)
+        .sum()
+}
+
+fn imbalance(bid_depth: f64, ask_depth: f64) -> f64 {
+    let total = bid_depth + ask_depth;
+
+    if total > 0.0 { (bid_depth - ask_depth) / total } else { 0.0 }
+}
+// Average price and total size, the levels must not be empty
+fn vwap(levels: &[RestingOrder]) -> (f64, f64) {
+    let size: f64 = levels.iter().map(|order| order.size).sum();
+    let notional: f64 = levels.iter().map(|order| order.price * order.size).sum();
+
+    (notional / size, size)
+}
+
+fn skew(bid_depth: f64, ask_depth: f64) -> f64 {
+    match (bid_depth > 0.0, ask_depth > 0.0) {
+        (true, true) => (bid_depth.ln() - ask_depth.ln()).clamp(-MAX_SKEW, MAX_SKEW),
+        (true, false) => MAX_SKEW,
+        (false, true) => -MAX_SKEW,
+        (false, false) => 0.0
     }
-}
\ No newline at end of file
+}
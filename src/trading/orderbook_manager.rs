@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::time::{ Duration, Instant };
+use crate::trading::instrument::InstrumentSpec;
+use crate::trading::market::Category;
+use crate::trading::orderbook::{ Orderbook, OrderbookError, RestingOrder };
+use crate::trading::public_stream::{ OrderbookUpdate, PublicEvent };
+/*
+
+Owns one Orderbook per (category, symbol) and routes public stream updates to them.
+Tracks which books have lost sync or stopped updating, and answers queries across books
+
+*/
+
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BookKey {
+    pub category: Category,
+    pub symbol: String
+}
+
+impl BookKey {
+
+    pub fn new(category: Category, symbol: &str) -> BookKey {
+        BookKey { category, symbol: symbol.to_string() }
+    }
+}
+
+// Best level on each side of one book, None for an empty side
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopOfBook {
+    pub key: BookKey,
+    pub bid: Option<RestingOrder>,
+    pub ask: Option<RestingOrder>
+}
+
+#[derive(Debug)]
+struct TrackedBook {
+    book: Orderbook,
+    // Local receive time of the last applied update
+    received: Option<Instant>,
+    gaps: u64
+}
+
+#[derive(Debug)]
+pub struct OrderbookManager {
+    books: HashMap<BookKey, TrackedBook>,
+    specs: HashMap<BookKey, InstrumentSpec>,
+    max_age: Duration
+}
+
+impl Default for OrderbookManager {
+    fn default() -> OrderbookManager {
+        OrderbookManager::new()
+    }
+}
+
+impl OrderbookManager {
+
+    pub fn new() -> OrderbookManager {
+        OrderbookManager {
+            books: HashMap::new(),
+            specs: HashMap::new(),
+            max_age: DEFAULT_MAX_AGE
+        }
+    }
+    // A book with no update for longer than max_age is reported by stale_books
+    pub fn with_max_age(mut self, max_age: Duration) -> OrderbookManager {
+        self.max_age = max_age;
+        self
+    }
+    // Books created for this symbol are keyed on its tick grid
+    pub fn with_spec(mut self, category: Category, spec: InstrumentSpec) -> OrderbookManager {
+        self.specs.insert(BookKey::new(category, &spec.symbol), spec);
+        self
+    }
+    // Starts tracking a book before its first snapshot arrives
+    pub fn track(&mut self, category: Category, symbol: &str) {
+        self.tracked(BookKey::new(category, symbol));
+    }
+    // Stops tracking a book and hands it back
+    pub fn remove(&mut self, category: Category, symbol: &str) -> Option<Orderbook> {
+        self.books
+            .remove(&BookKey::new(category, symbol))
+            .map(|tracked| tracked.book)
+    }
+    // Applies a snapshot or delta to its symbol's book, creating the book if it's new.
+    // On a gap the book stays out of sync until the next snapshot, see out_of_sync
+    pub fn apply(&mut self, category: Category, update: &OrderbookUpdate) -> Result<(), OrderbookError> {
+        let tracked = self.tracked(BookKey::new(category, &update.symbol));
+
+        match update.apply_to(&mut tracked.book) {
+            Ok(()) => {
+                tracked.received = Some(Instant::now());
+                Ok(())
+            }
+
+            Err(e) => {
+                if let OrderbookError::Gap { .. } = e {
+                    tracked.gaps += 1;
+                }
+
+                Err(e)
+            }
+        }
+    }
+    // Routes orderbook events from a category's public stream, other events are ignored
+    pub fn apply_event(&mut self, category: Category, event: &PublicEvent) -> Result<(), OrderbookError> {
+        match event {
+            PublicEvent::Orderbook(update) => self.apply(category, update),
+            _ => Ok(())
+        }
+    }
+
+    fn tracked(&mut self, key: BookKey) -> &mut TrackedBook {
+        let spec = self.specs.get(&key).cloned();
+
+        self.books
+            .entry(key)
+            .or_insert_with(|| {
+                let book = match spec {
+                    Some(spec) => Orderbook::new().with_spec(spec),
+                    None => Orderbook::new()
+                };
+
+                TrackedBook { book, received: None, gaps: 0 }
+            })
+    }
+
+    pub fn book(&self, category: Category, symbol: &str) -> Option<&Orderbook> {
+        self.books
+            .get(&BookKey::new(category, symbol))
+            .map(|tracked| &tracked.book)
+    }
+
+    pub fn keys(&self) -> Vec<BookKey> {
+        let mut keys: Vec<BookKey> = self.books.keys().cloned().collect();
+        keys.sort_by(|a, b| (&a.symbol, a.category.as_str()).cmp(&(&b.symbol, b.category.as_str())));
+        keys
+    }
+
+    pub fn len(&self) -> usize {
+        self.books.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.books.is_empty()
+    }
+    // Time since the book last applied an update, None if it never has
+    pub fn age(&self, category: Category, symbol: &str) -> Option<Duration> {
+        self.books
+            .get(&BookKey::new(category, symbol))?
+            .received
+            .map(|received| received.elapsed())
+    }
+    // Number of update id gaps the book has hit, each one needs a resubscribe for a new snapshot
+    pub fn gaps(&self, category: Category, symbol: &str) -> u64 {
+        self.books
+            .get(&BookKey::new(category, symbol))
+            .map_or(0, |tracked| tracked.gaps)
+    }
+    // Books waiting on a snapshot, either new or after a gap
+    pub fn out_of_sync(&self) -> Vec<BookKey> {
+        self.keys()
+            .into_iter()
+            .filter(|key| self.books[key].book.update_id().is_none())
+            .collect()
+    }
+    // Books that never updated or haven't updated within max_age
+    pub fn stale_books(&self) -> Vec<BookKey> {
+        self.keys()
+            .into_iter()
+            .filter(|key| {
+                self.books[key].received.is_none_or(|received| received.elapsed() > self.max_age)
+            })
+            .collect()
+    }
+    // Best bid and ask of every tracked book
+    pub fn top_of_book(&self) -> Vec<TopOfBook> {
+        self.keys()
+            .into_iter()
+            .map(|key| {
+                let book = &self.books[&key].book;
+
+                TopOfBook { bid: book.get_bid(), ask: book.get_ask(), key }
+            })
+            .collect()
+    }
+    // Highest bid for a symbol across every category it's tracked in e.g. spot vs linear
+    pub fn best_bid(&self, symbol: &str) -> Option<(Category, RestingOrder)> {
+        self.books_for(symbol)
+            .filter_map(|(category, book)| book.get_bid().map(|bid| (category, bid)))
+            .max_by(|a, b| a.1.price.total_cmp(&b.1.price))
+    }
+    // Lowest ask for a symbol across every category it's tracked in
+    pub fn best_ask(&self, symbol: &str) -> Option<(Category, RestingOrder)> {
+        self.books_for(symbol)
+            .filter_map(|(category, book)| book.get_ask().map(|ask| (category, ask)))
+            .min_by(|a, b| a.1.price.total_cmp(&b.1.price))
+    }
+
+    fn books_for<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = (Category, &'a Orderbook)> + 'a {
+        self.books
+            .iter()
+            .filter(move |(key, _tracked)| key.symbol == symbol)
+            .map(|(key, tracked)| (key.category, &tracked.book))
+    }
+}
@@ -0,0 +1,397 @@
+use serde::{ Deserialize, Serialize, Serializer, de::{ self, DeserializeOwned } };
+use serde_json::Value;
+use crate::trading::executor::{ ApiResponse, BuildRequest, Executor, ExecutorError };
+use crate::trading::instrument::{ InstrumentSpec, Price, Qty };
+use crate::trading::market::{ Category, Side };
+use crate::trading::oms::{ Order, OrderState };
+use crate::trading::ratelimit::EndpointGroup;
+use crate::trading::trade_stream::OrderTransport;
+/*
+
+Typed Bybit v5 order entry endpoints under /v5/order, these are all private and signed.
+Create, amend and cancel go over the WebSocket trade session when the executor is configured for it
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderType {
+    Market,
+    Limit
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    GTC,
+    IOC,
+    FOK,
+    PostOnly
+}
+
+// Orders are referenced either by the exchange id or by our own orderLinkId
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderRef {
+    OrderId(String),
+    OrderLinkId(String)
+}
+
+// Prices and quantities are ticks and lots of spec, they're sent at exactly its precision
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaceOrderRequest {
+    pub spec: InstrumentSpec,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub qty: Qty,
+    // Ignored by the exchange for market orders
+    pub price: Option<Price>,
+    pub time_in_force: Option<TimeInForce>,
+    pub reduce_only: Option<bool>,
+    pub order_link_id: Option<String>,
+    // 0 = one-way mode, 1 = hedge mode buy side, 2 = hedge mode sell side
+    pub position_idx: Option<u8>
+}
+
+// What a PlaceOrderRequest looks like on the wire
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaceOrderBody<'a> {
+    symbol: &'a str,
+    side: Side,
+    order_type: OrderType,
+    qty: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_in_force: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reduce_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order_link_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position_idx: Option<u8>
+}
+
+impl Serialize for PlaceOrderRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let body = PlaceOrderBody {
+            symbol: &self.spec.symbol,
+            side: self.side,
+            order_type: self.order_type,
+            qty: self.spec.format_qty(self.qty),
+            price: self.price.map(|price| self.spec.format_price(price)),
+            time_in_force: self.time_in_force,
+            reduce_only: self.reduce_only,
+            order_link_id: self.order_link_id.as_deref(),
+            position_idx: self.position_idx
+        };
+
+        body.serialize(serializer)
+    }
+}
+
+impl PlaceOrderRequest {
+
+    pub fn limit(spec: &InstrumentSpec, side: Side, qty: Qty, price: Price) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            spec: spec.clone(),
+            side,
+            order_type: OrderType::Limit,
+            qty,
+            price: Some(price),
+            time_in_force: None,
+            reduce_only: None,
+            order_link_id: None,
+            position_idx: None
+        }
+    }
+
+    pub fn market(spec: &InstrumentSpec, side: Side, qty: Qty) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            spec: spec.clone(),
+            side,
+            order_type: OrderType::Market,
+            qty,
+            price: None,
+            time_in_force: None,
+            reduce_only: None,
+            order_link_id: None,
+            position_idx: None
+        }
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> PlaceOrderRequest {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn reduce_only(mut self) -> PlaceOrderRequest {
+        self.reduce_only = Some(true);
+        self
+    }
+
+    pub fn order_link_id(mut self, order_link_id: &str) -> PlaceOrderRequest {
+        self.order_link_id = Some(order_link_id.to_string());
+        self
+    }
+
+    pub fn position_idx(mut self, position_idx: u8) -> PlaceOrderRequest {
+        self.position_idx = Some(position_idx);
+        self
+    }
+    // Builds the oms entry for this request once the exchange has acknowledged it, it stays
+    // PendingNew until the order stream confirms it
+    pub fn to_pending_order(&self, ack: &OrderAck, created_time: u64) -> Order {
+        Order::new(
+            &self.spec,
+            ack.order_id.clone(),
+            self.side,
+            self.price.unwrap_or_default(),
+            self.qty,
+            created_time,
+            created_time
+        )
+            .with_position_idx(self.position_idx.unwrap_or(0))
+            .with_order_link_id(&ack.order_link_id)
+            .with_state(OrderState::PendingNew)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmendOrderRequest {
+    pub spec: InstrumentSpec,
+    pub order: OrderRef,
+    pub qty: Option<Qty>,
+    pub price: Option<Price>
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AmendOrderBody<'a> {
+    symbol: &'a str,
+    #[serde(flatten)]
+    order: &'a OrderRef,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price: Option<String>
+}
+
+impl Serialize for AmendOrderRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let body = AmendOrderBody {
+            symbol: &self.spec.symbol,
+            order: &self.order,
+            qty: self.qty.map(|qty| self.spec.format_qty(qty)),
+            price: self.price.map(|price| self.spec.format_price(price))
+        };
+
+        body.serialize(serializer)
+    }
+}
+
+impl AmendOrderRequest {
+
+    pub fn new(spec: &InstrumentSpec, order: OrderRef) -> AmendOrderRequest {
+        AmendOrderRequest {
+            spec: spec.clone(),
+            order,
+            qty: None,
+            price: None
+        }
+    }
+
+    pub fn qty(mut self, qty: Qty) -> AmendOrderRequest {
+        self.qty = Some(qty);
+        self
+    }
+
+    pub fn price(mut self, price: Price) -> AmendOrderRequest {
+        self.price = Some(price);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelOrderRequest {
+    pub symbol: String,
+    #[serde(flatten)]
+    pub order: OrderRef
+}
+
+impl CancelOrderRequest {
+
+    pub fn new(symbol: &str, order: OrderRef) -> CancelOrderRequest {
+        CancelOrderRequest {
+            symbol: symbol.to_string(),
+            order
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderAck {
+    pub order_id: String,
+    pub order_link_id: String
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct OrderAckList {
+    pub list: Vec<OrderAck>
+}
+
+// Single order endpoints take the category alongside the order fields
+#[derive(Serialize)]
+struct WithCategory<'a, T> {
+    category: Category,
+    #[serde(flatten)]
+    request: &'a T
+}
+
+// Batch endpoints take the category once and the orders under "request"
+#[derive(Serialize)]
+struct Batch<'a, T> {
+    category: Category,
+    request: &'a [T]
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelAll<'a> {
+    category: Category,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settle_coin: Option<&'a str>
+}
+
+#[derive(Deserialize)]
+struct BatchItemStatus {
+    code: i64,
+    msg: String
+}
+
+#[derive(Deserialize)]
+struct BatchStatus {
+    list: Vec<BatchItemStatus>
+}
+
+// Pairs every batch ack with its per order status from retExtInfo, a status list of a different
+// length can't be matched up and is a decode error
+pub fn batch_results(acks: Vec<OrderAck>, ret_ext_info: Value) -> Result<Vec<Result<OrderAck, ExecutorError>>, ExecutorError> {
+    let status = serde_json::from_value::<BatchStatus>(ret_ext_info)?;
+
+    if status.list.len() != acks.len() {
+        return Err(ExecutorError::Decode(de::Error::custom(format!(
+            "{} batch results but {} statuses", acks.len(), status.list.len()
+        ))));
+    }
+
+    let results = acks
+        .into_iter()
+        .zip(status.list)
+        .map(|(ack, status)| {
+            match status.code {
+                0 => Ok(ack),
+                code => Err(ExecutorError::Exchange { code, msg: status.msg })
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
+impl Executor {
+    // Sends an order request over REST or the trade session depending on the order transport
+    async fn submit<R: DeserializeOwned, B: Serialize>(&self, path: &str, op: &str, body: B) -> Result<ApiResponse<R>, ExecutorError> {
+        match self.order_transport() {
+            OrderTransport::Rest => {
+                self.fetch(BuildRequest::post(path).body(body).private()).await
+            }
+
+            OrderTransport::WebSocket => {
+                let session = self
+                    .trade_session()
+                    .ok_or_else(|| ExecutorError::InvalidRequest("no trade session configured".to_string()))?;
+
+                // The exchange counts WebSocket orders against the same per endpoint limits
+                self.rate_limiter()
+                    .acquire(EndpointGroup::from_path(path))
+                    .await?;
+
+                session.request(op, body).await
+            }
+        }
+    }
+    // POST /v5/order/create
+    pub async fn place_order(&self, category: Category, request: &PlaceOrderRequest) -> Result<OrderAck, ExecutorError> {
+        let body = WithCategory { category, request };
+
+        let resp = self
+            .submit::<OrderAck, _>("/v5/order/create", "order.create", body)
+            .await?;
+
+        Ok(resp.result)
+    }
+    // POST /v5/order/amend
+    pub async fn amend_order(&self, category: Category, request: &AmendOrderRequest) -> Result<OrderAck, ExecutorError> {
+        let body = WithCategory { category, request };
+
+        let resp = self
+            .submit::<OrderAck, _>("/v5/order/amend", "order.amend", body)
+            .await?;
+
+        Ok(resp.result)
+    }
+    // POST /v5/order/cancel
+    pub async fn cancel_order(&self, category: Category, request: &CancelOrderRequest) -> Result<OrderAck, ExecutorError> {
+        let body = WithCategory { category, request };
+
+        let resp = self
+            .submit::<OrderAck, _>("/v5/order/cancel", "order.cancel", body)
+            .await?;
+
+        Ok(resp.result)
+    }
+    // POST /v5/order/cancel-all, linear and inverse require either a symbol or a settle coin
+    pub async fn cancel_all_orders(&self, category: Category, symbol: Option<&str>, settle_coin: Option<&str>) -> Result<Vec<OrderAck>, ExecutorError> {
+        let body = CancelAll { category, symbol, settle_coin };
+
+        let resp = self
+            .fetch::<OrderAckList, _>(BuildRequest::post("/v5/order/cancel-all").body(body).private())
+            .await?;
+
+        Ok(resp.result.list)
+    }
+    // POST /v5/order/create-batch, results are returned in request order
+    pub async fn place_batch_orders(&self, category: Category, requests: &[PlaceOrderRequest]) -> Result<Vec<Result<OrderAck, ExecutorError>>, ExecutorError> {
+        let body = Batch { category, request: requests };
+
+        let resp = self
+            .submit::<OrderAckList, _>("/v5/order/create-batch", "order.create-batch", body)
+            .await?;
+
+        batch_results(resp.result.list, resp.ret_ext_info)
+    }
+    // POST /v5/order/amend-batch, results are returned in request order
+    pub async fn amend_batch_orders(&self, category: Category, requests: &[AmendOrderRequest]) -> Result<Vec<Result<OrderAck, ExecutorError>>, ExecutorError> {
+        let body = Batch { category, request: requests };
+
+        let resp = self
+            .submit::<OrderAckList, _>("/v5/order/amend-batch", "order.amend-batch", body)
+            .await?;
+
+        batch_results(resp.result.list, resp.ret_ext_info)
+    }
+    // POST /v5/order/cancel-batch, results are returned in request order
+    pub async fn cancel_batch_orders(&self, category: Category, requests: &[CancelOrderRequest]) -> Result<Vec<Result<OrderAck, ExecutorError>>, ExecutorError> {
+        let body = Batch { category, request: requests };
+
+        let resp = self
+            .submit::<OrderAckList, _>("/v5/order/cancel-batch", "order.cancel-batch", body)
+            .await?;
+
+        batch_results(resp.result.list, resp.ret_ext_info)
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{ BTreeMap, HashMap, VecDeque };
+use crate::trading::fees::FeeSchedule;
+use crate::trading::market::{ Category, Side };
+use crate::trading::orderbook::{ Orderbook, OrderbookError };
+use crate::trading::private_stream::{ ExecutionUpdate, PrivateEvent };
+/*
+
+Positions built from fills rather than resting orders. Quantities are signed, long is
+positive, and PnL and fees are in the quote currency of linear contracts. Realized PnL is
+net of trading fees and funding payments
+
+*/
+
+// Positions within this of zero are flat
+const QTY_EPSILON: f64 = 1e-12;
+
+const DAY_MS: u64 = 86_400_000;
+
+// How closing fills are matched against the open quantity for realized PnL
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CostBasis {
+    #[default]
+    Fifo, // Closes the oldest open lot first
+    AverageCost // Every open lot shares one average entry price
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    pub symbol: String,
+    pub side: Side,
+    pub qty: f64,
+    pub price: f64,
+    pub time: u64,
+    // Positive is paid, negative a rebate. None charges the keeper's fee schedule
+    pub fee: Option<f64>,
+    pub is_maker: bool
+}
+
+impl Fill {
+    // A taker fill with its fee left to the fee schedule
+    pub fn new(symbol: &str, side: Side, qty: f64, price: f64, time: u64) -> Fill {
+        Fill { symbol: symbol.to_string(), side, qty, price, time, fee: None, is_maker: false }
+    }
+
+    pub fn with_fee(mut self, fee: f64) -> Fill {
+        self.fee = Some(fee);
+        self
+    }
+
+    pub fn with_maker(mut self, is_maker: bool) -> Fill {
+        self.is_maker = is_maker;
+        self
+    }
+    // Every linear execution except funding and session settlement changes the position,
+    // including AdlTrade, BustTrade, Delivery, BlockTrade and MovePosition. Spot fees can be
+    // charged in the base coin and inverse PnL is in the base coin, neither fits a quote
+    // currency position so they are skipped
+    pub fn from_execution(execution: &ExecutionUpdate) -> Option<Fill> {
+        if execution.category != Category::Linear || matches!(execution.exec_type.as_str(), "Funding" | "Settle") {
+            return None;
+        }
+
+        let fill = Fill::new(&execution.symbol, execution.side, execution.exec_qty, execution.exec_price, execution.exec_time)
+            .with_fee(execution.exec_fee)
+            .with_maker(execution.is_maker);
+
+        Some(fill)
+    }
+
+    fn signed_qty(&self) -> f64 {
+        match self.side {
+            Side::Buy => self.qty,
+            Side::Sell => -self.qty
+        }
+    }
+}
+
+// Funding settled on a perpetual position, positive is paid like a fee
+#[derive(Clone, Debug, PartialEq)]
+pub struct FundingPayment {
+    pub symbol: String,
+    pub amount: f64,
+    pub time: u64
+}
+
+impl FundingPayment {
+
+    pub fn new(symbol: &str, amount: f64, time: u64) -> FundingPayment {
+        FundingPayment { symbol: symbol.to_string(), amount, time }
+    }
+    // Bybit settles funding as an execution with exec_type Funding and the payment in exec_fee,
+    // linear only like Fill::from_execution
+    pub fn from_execution(execution: &ExecutionUpdate) -> Option<FundingPayment> {
+        if execution.category != Category::Linear || execution.exec_type != "Funding" {
+            return None;
+        }
+
+        Some(FundingPayment::new(&execution.symbol, execution.exec_fee, execution.exec_time))
+    }
+}
+
+// Realized PnL split by where it came from, for a position, a day or a single fill
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct PnlBreakdown {
+    // PnL of closed quantity before fees
+    pub trading: f64,
+    pub fees: f64,
+    pub funding: f64
+}
+
+impl PnlBreakdown {
+    // Trading PnL less fees and funding paid
+    pub fn net(&self) -> f64 {
+        self.trading - self.fees - self.funding
+    }
+}
+
+// Open quantity at one entry price, signed like the position
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Lot {
+    qty: f64,
+    price: f64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub symbol: String,
+    cost_basis: CostBasis,
+    lots: VecDeque<Lot>,
+    pnl: PnlBreakdown,
+    last_fill_time: u64
+}
+
+impl Position {
+
+    pub fn new(symbol: &str, cost_basis: CostBasis) -> Position {
+        Position {
+            symbol: symbol.to_string(),
+            cost_basis,
+            lots: VecDeque::new(),
+            pnl: PnlBreakdown::default(),
+            last_fill_time: 0
+        }
+    }
+    // Closes against open lots first, anything left opens (or flips) the position at the
+    // fill price. Returns the PnL realized by this fill and its fee, a missing fee is 0
+    pub fn apply_fill(&mut self, fill: &Fill) -> PnlBreakdown {
+        let mut remaining = fill.signed_qty();
+        let mut realized = 0.0;
+
+        while remaining.abs() > QTY_EPSILON {
+            let Some(lot) = self.lots.front_mut() else { break };
+
+            // Same direction, nothing left to close
+            if lot.qty.signum() == remaining.signum() {
+                break;
+            }
+
+            let closed = lot.qty.abs().min(remaining.abs()) * lot.qty.signum();
+
+            realized += (fill.price - lot.price) * closed;
+            lot.qty -= closed;
+            remaining += closed;
+
+            if lot.qty.abs() <= QTY_EPSILON {
+                self.lots.pop_front();
+            }
+        }
+
+        if remaining.abs() > QTY_EPSILON {
+            self.open(Lot { qty: remaining, price: fill.price });
+        }
+
+        let pnl = PnlBreakdown { trading: realized, fees: fill.fee.unwrap_or(0.0), funding: 0.0 };
+
+        self.pnl.trading += pnl.trading;
+        self.pnl.fees += pnl.fees;
+        self.last_fill_time = self.last_fill_time.max(fill.time);
+
+        pnl
+    }
+    // Funding doesn't change the open quantity. Returns the payment under funding
+    pub fn apply_funding(&mut self, funding: &FundingPayment) -> PnlBreakdown {
+        self.pnl.funding += funding.amount;
+
+        PnlBreakdown { funding: funding.amount, ..PnlBreakdown::default() }
+    }
+
+    fn open(&mut self, lot: Lot) {
+        match (self.cost_basis, self.lots.back_mut()) {
+            (CostBasis::AverageCost, Some(open)) => {
+                let qty = open.qty + lot.qty;
+
+                open.price = (open.price * open.qty + lot.price * lot.qty) / qty;
+                open.qty = qty;
+            }
+
+            _ => self.lots.push_back(lot)
+        }
+    }
+    // Signed net quantity, positive when long
+    pub fn net_qty(&self) -> f64 {
+        self.lots.iter().map(|lot| lot.qty).sum()
+    }
+
+    pub fn side(&self) -> Option<Side> {
+        let net_qty = self.net_qty();
+
+        if net_qty > QTY_EPSILON {
+            Some(Side::Buy)
+        } else if net_qty < -QTY_EPSILON {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.side().is_none()
+    }
+    // Quantity weighted entry price of what's still open, None when flat
+    pub fn avg_entry_price(&self) -> Option<f64> {
+        let net_qty = self.net_qty();
+
+        if net_qty.abs() <= QTY_EPSILON {
+            return None;
+        }
+
+        let cost: f64 = self.lots.iter().map(|lot| lot.qty * lot.price).sum();
+
+        Some(cost / net_qty)
+    }
+
+    // Net of fees and funding
+    pub fn realized_pnl(&self) -> f64 {
+        self.pnl.net()
+    }
+
+    pub fn pnl_breakdown(&self) -> PnlBreakdown {
+        self.pnl
+    }
+    // PnL of the open quantity if it were closed at mark_price
+    pub fn unrealized_pnl(&self, mark_price: f64) -> f64 {
+        self.lots
+            .iter()
+            .map(|lot| (mark_price - lot.price) * lot.qty)
+            .sum()
+    }
+    // Unrealized PnL marked against the book's mid price
+    pub fn unrealized_pnl_at_mid(&self, orderbook: &Orderbook) -> Result<f64, OrderbookError> {
+        Ok(self.unrealized_pnl(orderbook.get_mid_price()?))
+    }
+
+    pub fn last_fill_time(&self) -> u64 {
+        self.last_fill_time
+    }
+}
+
+// One position per symbol, all using the same cost basis
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct PositionKeeper {
+    cost_basis: CostBasis,
+    fee_schedule: FeeSchedule,
+    positions: HashMap<String, Position>,
+    // Keyed by the start of the UTC day in ms
+    daily: BTreeMap<u64, PnlBreakdown>
+}
+
+impl PositionKeeper {
+
+    pub fn new(cost_basis: CostBasis) -> PositionKeeper {
+        PositionKeeper {
+            cost_basis,
+            fee_schedule: FeeSchedule::default(),
+            positions: HashMap::new(),
+            daily: BTreeMap::new()
+        }
+    }
+    // Charged on fills that don't carry their own fee
+    pub fn with_fee_schedule(mut self, fee_schedule: FeeSchedule) -> PositionKeeper {
+        self.fee_schedule = fee_schedule;
+        self
+    }
+
+    pub fn cost_basis(&self) -> CostBasis {
+        self.cost_basis
+    }
+
+    pub fn fee_schedule(&self) -> &FeeSchedule {
+        &self.fee_schedule
+    }
+
+    fn position_mut(&mut self, symbol: &str) -> &mut Position {
+        let cost_basis = self.cost_basis;
+
+        self.positions
+            .entry(symbol.to_string())
+            .or_insert_with(|| Position::new(symbol, cost_basis))
+    }
+
+    fn day_mut(&mut self, time: u64) -> &mut PnlBreakdown {
+        self.daily
+            .entry(time - time % DAY_MS)
+            .or_default()
+    }
+    // Returns the PnL realized by this fill and the fee charged for it
+    pub fn apply_fill(&mut self, fill: &Fill) -> PnlBreakdown {
+        let fee = fill.fee.unwrap_or_else(|| self.fee_schedule.fee(&fill.symbol, fill.qty, fill.price, fill.is_maker));
+        let fill = fill.clone().with_fee(fee);
+
+        let pnl = self
+            .position_mut(&fill.symbol)
+            .apply_fill(&fill);
+
+        let day = self.day_mut(fill.time);
+
+        day.trading += pnl.trading;
+        day.fees += pnl.fees;
+
+        pnl
+    }
+
+    pub fn apply_funding(&mut self, funding: &FundingPayment) -> PnlBreakdown {
+        self.day_mut(funding.time).funding += funding.amount;
+
+        self.position_mut(&funding.symbol)
+            .apply_funding(funding)
+    }
+    // Applies linear trade and funding executions from the private stream, other events are ignored
+    pub fn apply_private_event(&mut self, event: &PrivateEvent) {
+        if let PrivateEvent::Executions(executions) = event {
+            for execution in executions {
+                if let Some(fill) = Fill::from_execution(execution) {
+                    self.apply_fill(&fill);
+                } else if let Some(funding) = FundingPayment::from_execution(execution) {
+                    self.apply_funding(&funding);
+                }
+            }
+        }
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+    // Signed net quantity, 0 for symbols with no fills
+    pub fn net_qty(&self, symbol: &str) -> f64 {
+        self.position(symbol).map_or(0.0, Position::net_qty)
+    }
+    // Realized PnL summed over every symbol, net of fees and funding
+    pub fn realized_pnl(&self) -> f64 {
+        self.positions.values().map(Position::realized_pnl).sum()
+    }
+    // Realized PnL per UTC day in ascending order, days are keyed by their start in ms
+    pub fn daily_pnl(&self) -> &BTreeMap<u64, PnlBreakdown> {
+        &self.daily
+    }
+    // Unrealized PnL of every position with a mark price, positions without one are skipped
+    pub fn unrealized_pnl(&self, mark_prices: &HashMap<String, f64>) -> f64 {
+        self.positions
+            .values()
+            .filter_map(|position| {
+                mark_prices
+                    .get(&position.symbol)
+                    .map(|mark_price| position.unrealized_pnl(*mark_price))
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use url::Url;
+use serde::{ Deserialize, Deserializer };
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedReceiver;
+use crate::trading::decode::{ f64_from_str, opt_f64_from_str, u64_from_str };
+use crate::trading::executor::Executor;
+use crate::trading::market::{ Category, Side };
+use crate::trading::orders::{ OrderType, TimeInForce };
+use crate::trading::websocket::{ self, Protocol, RealtimeAuth, StreamConfig, StreamEvent, StreamHandle };
+/*
+
+Authenticated Bybit v5 private WebSocket stream (order, execution, position, wallet).
+Order events can be fed straight into Oms::apply_private_event
+
+*/
+
+pub const MAINNET_PRIVATE_URL: &str = "wss://stream.bybit.com/v5/private";
+pub const TESTNET_PRIVATE_URL: &str = "wss://stream-testnet.bybit.com/v5/private";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrivateTopic {
+    Order,
+    Execution,
+    Position,
+    Wallet
+}
+
+impl fmt::Display for PrivateTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivateTopic::Order => write!(f, "order"),
+            PrivateTopic::Execution => write!(f, "execution"),
+            PrivateTopic::Position => write!(f, "position"),
+            PrivateTopic::Wallet => write!(f, "wallet")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Untriggered,
+    Rejected,
+    PartiallyFilledCanceled,
+    Filled,
+    Cancelled,
+    Triggered,
+    Deactivated
+}
+
+impl OrderStatus {
+    // True while the order can still trade
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::Untriggered | OrderStatus::Triggered)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderUpdate {
+    pub category: Category,
+    pub order_id: String,
+    #[serde(default)]
+    pub order_link_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    #[serde(default)]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub leaves_qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub cum_exec_qty: f64,
+    // Empty until the first fill
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub avg_price: Option<f64>,
+    pub order_status: OrderStatus,
+    #[serde(default)]
+    pub reject_reason: String,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub position_idx: u8,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub created_time: u64,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub updated_time: u64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionUpdate {
+    pub category: Category,
+    pub symbol: String,
+    pub exec_id: String,
+    pub order_id: String,
+    #[serde(default)]
+    pub order_link_id: String,
+    pub side: Side,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub exec_price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub exec_qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub exec_fee: f64,
+    // Trade, Funding, AdlTrade, BustTrade etc.
+    pub exec_type: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub leaves_qty: f64,
+    pub is_maker: bool,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub exec_time: u64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionUpdate {
+    pub category: Category,
+    pub symbol: String,
+    // Empty when the position is flat
+    #[serde(deserialize_with = "opt_side")]
+    pub side: Option<Side>,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub size: f64,
+    #[serde(default)]
+    pub position_idx: u8,
+    #[serde(default, rename = "entryPrice", deserialize_with = "opt_f64_from_str")]
+    pub entry_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub mark_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub unrealised_pnl: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub cum_realised_pnl: Option<f64>,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub updated_time: u64
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoinBalance {
+    pub coin: String,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub equity: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub wallet_balance: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub unrealised_pnl: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub cum_realised_pnl: Option<f64>
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletUpdate {
+    pub account_type: String,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub total_equity: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub total_wallet_balance: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub total_available_balance: Option<f64>,
+    pub coin: Vec<CoinBalance>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrivateEvent {
+    Orders(Vec<OrderUpdate>),
+    Executions(Vec<ExecutionUpdate>),
+    Positions(Vec<PositionUpdate>),
+    Wallet(Vec<WalletUpdate>)
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    topic: String,
+    data: Value
+}
+
+fn opt_side<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Side>, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "Buy" => Ok(Some(Side::Buy)),
+        "Sell" => Ok(Some(Side::Sell)),
+        _ => Ok(None)
+    }
+}
+
+// Decodes a private stream data message, category scoped topics e.g. "order.linear" are included
+pub fn parse_private(message: Value) -> Result<Option<PrivateEvent>, serde_json::Error> {
+    let envelope: Envelope = serde_json::from_value(message)?;
+
+    let event = match envelope.topic.split('.').next() {
+        Some("order") => PrivateEvent::Orders(serde_json::from_value(envelope.data)?),
+        Some("execution") => PrivateEvent::Executions(serde_json::from_value(envelope.data)?),
+        Some("position") => PrivateEvent::Positions(serde_json::from_value(envelope.data)?),
+        Some("wallet") => PrivateEvent::Wallet(serde_json::from_value(envelope.data)?),
+        _ => return Ok(None)
+    };
+
+    Ok(Some(event))
+}
+
+struct PrivateProtocol {
+    auth: RealtimeAuth
+}
+
+impl Protocol for PrivateProtocol {
+    type Event = PrivateEvent;
+
+    fn auth_frame(&self) -> Option<String> {
+        Some(self.auth.fresh_auth_frame())
+    }
+
+    fn parse(&self, message: Value) -> Result<Option<PrivateEvent>, serde_json::Error> {
+        parse_private(message)
+    }
+}
+
+pub struct PrivateStream;
+
+impl PrivateStream {
+    // Authenticates with the executor's credentials then subscribes, on every (re)connect
+    pub fn connect(executor: &Executor, url: Url, topics: &[PrivateTopic], config: StreamConfig) -> (StreamHandle, UnboundedReceiver<StreamEvent<PrivateEvent>>) {
+        let topics = topics.iter().map(PrivateTopic::to_string).collect();
+        let auth = executor.realtime_auth();
+
+        websocket::spawn(url, PrivateProtocol { auth }, topics, config)
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+use url::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedReceiver;
+use crate::trading::decode::{ f64_from_str, opt_f64_from_str, opt_u64_from_str };
+use crate::trading::market::{ Category, Interval, Level, Side };
+use crate::trading::orderbook::{ Orderbook, OrderbookError };
+use crate::trading::websocket::{ self, Protocol, StreamConfig, StreamEvent, StreamHandle };
+/*
+
+Bybit v5 public WebSocket streams (orderbook, publicTrade, tickers, kline). Orderbook
+messages decode into snapshot/delta updates that keep an Orderbook live
+
+*/
+
+pub const MAINNET_PUBLIC_URL: &str = "wss://stream.bybit.com/v5/public";
+pub const TESTNET_PUBLIC_URL: &str = "wss://stream-testnet.bybit.com/v5/public";
+
+// Each category has its own public endpoint e.g. wss://stream.bybit.com/v5/public/linear
+pub fn public_url(base: &str, category: Category) -> Url {
+    Url::parse(&format!("{}/{}", base, category.as_str()))
+        .expect("Public stream url is valid")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Orderbook { depth: u32, symbol: String },
+    PublicTrade { symbol: String },
+    Tickers { symbol: String },
+    Kline { interval: Interval, symbol: String }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Orderbook { depth, symbol } => write!(f, "orderbook.{}.{}", depth, symbol),
+            Topic::PublicTrade { symbol } => write!(f, "publicTrade.{}", symbol),
+            Topic::Tickers { symbol } => write!(f, "tickers.{}", symbol),
+            Topic::Kline { interval, symbol } => write!(f, "kline.{}.{}", interval.as_str(), symbol)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    Snapshot,
+    Delta
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderbookUpdate {
+    pub kind: UpdateKind,
+    pub symbol: String,
+    // A size of 0 in a delta removes the level
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+    pub update_id: u64,
+    pub seq: u64,
+    pub ts: u64
+}
+
+impl OrderbookUpdate {
+    // Snapshots replace the whole book, deltas fail on an update id gap so the caller can resync
+    pub fn apply_to(&self, orderbook: &mut Orderbook) -> Result<(), OrderbookError> {
+        let ts = self.ts as u128;
+
+        match self.kind {
+            UpdateKind::Snapshot => {
+                orderbook.apply_snapshot(&self.bids, &self.asks, self.update_id, self.seq, ts);
+                Ok(())
+            }
+
+            UpdateKind::Delta => orderbook.apply_delta(&self.bids, &self.asks, self.update_id, self.seq, ts)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TradeUpdate {
+    #[serde(rename = "i")]
+    pub exec_id: String,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(rename = "v", deserialize_with = "f64_from_str")]
+    pub size: f64,
+    #[serde(rename = "S")]
+    pub side: Side,
+    #[serde(rename = "T")]
+    pub time: u64,
+    #[serde(default, rename = "BT")]
+    pub is_block_trade: bool
+}
+
+// Derivative ticker deltas only carry the fields that changed
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TickerUpdate {
+    pub symbol: String,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub last_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub bid1_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub bid1_size: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub ask1_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub ask1_size: Option<f64>,
+    #[serde(default, rename = "volume24h", deserialize_with = "opt_f64_from_str")]
+    pub volume_24h: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub index_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub mark_price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub open_interest: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_str")]
+    pub funding_rate: Option<f64>,
+    #[serde(default, deserialize_with = "opt_u64_from_str")]
+    pub next_funding_time: Option<u64>
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct KlineUpdate {
+    pub start: u64,
+    pub end: u64,
+    pub interval: Interval,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub open: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub close: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub high: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub low: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub volume: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub turnover: f64,
+    // True once the candle has closed
+    pub confirm: bool,
+    pub timestamp: u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PublicEvent {
+    Orderbook(OrderbookUpdate),
+    Trades(Vec<TradeUpdate>),
+    Ticker(TickerUpdate),
+    Kline { symbol: String, klines: Vec<KlineUpdate> }
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    topic: String,
+    #[serde(rename = "type")]
+    kind: Option<UpdateKind>,
+    ts: u64,
+    data: Value
+}
+
+#[derive(Deserialize)]
+struct OrderbookData {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "b")]
+    bids: Vec<Level>,
+    #[serde(rename = "a")]
+    asks: Vec<Level>,
+    #[serde(rename = "u")]
+    update_id: u64,
+    #[serde(default)]
+    seq: u64
+}
+
+// Decodes a public stream data message, returns None for topics we don't handle
+pub fn parse_public(message: Value) -> Result<Option<PublicEvent>, serde_json::Error> {
+    let envelope: Envelope = serde_json::from_value(message)?;
+    let mut parts = envelope.topic.split('.');
+
+    let event = match parts.next() {
+        Some("orderbook") => {
+            let data: OrderbookData = serde_json::from_value(envelope.data)?;
+
+            PublicEvent::Orderbook(OrderbookUpdate {
+                kind: envelope.kind.unwrap_or(UpdateKind::Snapshot),
+                symbol: data.symbol,
+                bids: data.bids,
+                asks: data.asks,
+                update_id: data.update_id,
+                seq: data.seq,
+                ts: envelope.ts
+            })
+        }
+
+        Some("publicTrade") => PublicEvent::Trades(serde_json::from_value(envelope.data)?),
+
+        Some("tickers") => PublicEvent::Ticker(serde_json::from_value(envelope.data)?),
+
+        Some("kline") => PublicEvent::Kline {
+            symbol: parts.next_back().unwrap_or_default().to_string(),
+            klines: serde_json::from_value(envelope.data)?
+        },
+
+        _ => return Ok(None)
+    };
+
+    Ok(Some(event))
+}
+
+struct PublicProtocol;
+
+impl Protocol for PublicProtocol {
+    type Event = PublicEvent;
+
+    fn parse(&self, message: Value) -> Result<Option<PublicEvent>, serde_json::Error> {
+        parse_public(message)
+    }
+}
+
+pub struct PublicStream;
+
+impl PublicStream {
+    // Connects in the background and subscribes to topics, resubscribing after every reconnect
+    pub fn connect(url: Url, topics: &[Topic], config: StreamConfig) -> (StreamHandle, UnboundedReceiver<StreamEvent<PublicEvent>>) {
+        let topics = topics.iter().map(Topic::to_string).collect();
+
+        websocket::spawn(url, PublicProtocol, topics, config)
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::Mutex;
+use std::collections::HashMap;
+use reqwest::header::HeaderMap;
+use std::time::{ Duration, Instant };
+use crate::trading::executor::ExecutorError;
+/*
+
+Client side token bucket rate limiter used by the Executor, one bucket per endpoint group.
+Buckets are tightened with the X-Bapi-Limit* headers Bybit returns on private endpoints
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EndpointGroup {
+    MarketData,
+    OrderCreate, // Create and amend
+    OrderCancel,
+    Account // Everything else e.g. positions, wallet, order queries
+}
+
+impl EndpointGroup {
+    // Maps a request path e.g. "/v5/order/create" to its group
+    pub fn from_path(path: &str) -> EndpointGroup {
+        if path.starts_with("/v5/market/") {
+            EndpointGroup::MarketData
+        } else if path.starts_with("/v5/order/create") || path.starts_with("/v5/order/amend") {
+            EndpointGroup::OrderCreate
+        } else if path.starts_with("/v5/order/cancel") {
+            EndpointGroup::OrderCancel
+        } else {
+            EndpointGroup::Account
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    Wait, // Callers sleep until a permit is available
+    FailFast // Callers get ExecutorError::RateLimited straight away
+}
+
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+    // Set when the exchange reports the limit as exhausted
+    blocked_until: Option<Instant>
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_sec: f64) -> TokenBucket {
+        TokenBucket {
+            capacity,
+            refill_per_sec,
+            tokens: capacity,
+            last_refill: Instant::now(),
+            blocked_until: None
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+    // Takes a token or returns how long until one is available
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(blocked_until) = self.blocked_until {
+            if now < blocked_until {
+                return Err(blocked_until - now);
+            }
+
+            self.blocked_until = None;
+            self.tokens = self.capacity;
+            self.last_refill = now;
+        }
+
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let missing = 1.0 - self.tokens;
+
+        Err(Duration::from_secs_f64(missing / self.refill_per_sec))
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    policy: RateLimitPolicy,
+    buckets: Mutex<HashMap<EndpointGroup, TokenBucket>>
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(RateLimitPolicy::Wait)
+    }
+}
+
+impl RateLimiter {
+    // Starts from Bybit's published defaults, 600 requests per 5s per ip
+    // for market data and 10 requests per second per uid for trading
+    pub fn new(policy: RateLimitPolicy) -> RateLimiter {
+        let buckets = HashMap::from([
+            (EndpointGroup::MarketData, TokenBucket::new(120.0, 120.0)),
+            (EndpointGroup::OrderCreate, TokenBucket::new(10.0, 10.0)),
+            (EndpointGroup::OrderCancel, TokenBucket::new(10.0, 10.0)),
+            (EndpointGroup::Account, TokenBucket::new(10.0, 10.0))
+        ]);
+
+        RateLimiter {
+            policy,
+            buckets: Mutex::new(buckets)
+        }
+    }
+    // Overrides the burst capacity and refill rate of a group. A bucket needs room for one
+    // permit and a finite positive rate, otherwise it would never refill
+    pub fn limit(self, group: EndpointGroup, capacity: u32, refill_per_sec: f64) -> Result<RateLimiter, ExecutorError> {
+        if capacity < 1 || !refill_per_sec.is_finite() || refill_per_sec <= 0.0 {
+            return Err(ExecutorError::InvalidRequest(format!(
+                "invalid rate limit for {:?}: capacity {}, refill {} per second", group, capacity, refill_per_sec
+            )));
+        }
+
+        self.buckets
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .insert(group, TokenBucket::new(capacity as f64, refill_per_sec));
+
+        Ok(self)
+    }
+
+    pub fn policy(&self) -> RateLimitPolicy {
+        self.policy
+    }
+    // Returns the tokens currently available for a group
+    pub fn available(&self, group: EndpointGroup) -> f64 {
+        let mut buckets = self.buckets
+            .lock()
+            .expect("Rate limiter lock poisoned");
+
+        let bucket = buckets
+            .get_mut(&group)
+            .expect("Every endpoint group has a bucket");
+
+        bucket.refill(Instant::now());
+
+        match bucket.blocked_until {
+            Some(blocked_until) if Instant::now() < blocked_until => 0.0,
+            _ => bucket.tokens
+        }
+    }
+    // Takes a permit without waiting, returns how long until one is available on failure
+    pub fn try_acquire(&self, group: EndpointGroup) -> Result<(), Duration> {
+        self.buckets
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .get_mut(&group)
+            .expect("Every endpoint group has a bucket")
+            .try_take(Instant::now())
+    }
+    // Waits for a permit or fails straight away depending on the policy
+    pub async fn acquire(&self, group: EndpointGroup) -> Result<(), ExecutorError> {
+        loop {
+            let wait = match self.try_acquire(group) {
+                Ok(()) => return Ok(()),
+                Err(wait) => wait
+            };
+
+            if self.policy == RateLimitPolicy::FailFast {
+                return Err(ExecutorError::RateLimited {
+                    code: None,
+                    msg: format!("client side limit for {:?}, retry in {:?}", group, wait)
+                });
+            }
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+    // Tightens a bucket with X-Bapi-Limit, X-Bapi-Limit-Status and X-Bapi-Limit-Reset-Timestamp.
+    // The reset timestamp is on the exchange's clock so now_ms must be too, see TimeSync::now_ms
+    pub fn update_from_headers(&self, group: EndpointGroup, headers: &HeaderMap, now_ms: u128) {
+        let header = |name: &str| -> Option<u64> {
+            headers
+                .get(name)?
+                .to_str()
+                .ok()?
+                .parse::<u64>()
+                .ok()
+        };
+
+        let limit = header("X-Bapi-Limit");
+        let remaining = header("X-Bapi-Limit-Status");
+        let reset_timestamp = header("X-Bapi-Limit-Reset-Timestamp");
+
+        let mut buckets = self.buckets
+            .lock()
+            .expect("Rate limiter lock poisoned");
+
+        let bucket = buckets
+            .get_mut(&group)
+            .expect("Every endpoint group has a bucket");
+
+        let now = Instant::now();
+        bucket.refill(now);
+
+        if let Some(limit) = limit.filter(|limit| *limit > 0) {
+            bucket.capacity = limit as f64;
+            bucket.refill_per_sec = limit as f64;
+        }
+
+        if let Some(remaining) = remaining {
+            bucket.tokens = bucket.tokens.min(remaining as f64);
+
+            if remaining == 0 {
+                // Without a reset timestamp wait out one refill period
+                let wait = match reset_timestamp {
+                    Some(reset_timestamp) => Duration::from_millis((reset_timestamp as u128).saturating_sub(now_ms) as u64),
+                    None => Duration::from_secs_f64(1.0 / bucket.refill_per_sec)
+                };
+
+                bucket.blocked_until = Some(now + wait);
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use rand::Rng;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+/*
+
+Retry and failover support for the Executor. Idempotent requests are retried with
+exponential backoff and jitter, and the primary endpoint is swapped for the alternate
+one while it is failing, then tried again once its cooldown has passed
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2)
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Never retries, every request gets a single attempt
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        }
+    }
+    // Delay before the given retry (1 = first retry), base * 2^(retry - 1) capped at
+    // max_delay with half of it randomised so clients do not retry in lockstep
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31);
+        let delay = self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        let half = delay / 2;
+        let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+
+        half + half.mul_f64(jitter)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Primary,
+    Alternate
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        match self.unhealthy_until {
+            Some(unhealthy_until) => now >= unhealthy_until,
+            None => true
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Failover {
+    // Consecutive failures before an endpoint is taken out of rotation
+    failure_threshold: u32,
+    // How long an unhealthy endpoint is skipped before it is tried again
+    cooldown: Duration,
+    primary: Mutex<EndpointHealth>,
+    alternate: Mutex<EndpointHealth>
+}
+
+impl Default for Failover {
+    fn default() -> Failover {
+        Failover::new(1, Duration::from_secs(30))
+    }
+}
+
+impl Failover {
+
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Failover {
+        Failover {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            primary: Mutex::new(EndpointHealth::default()),
+            alternate: Mutex::new(EndpointHealth::default())
+        }
+    }
+
+    fn health(&self, endpoint: Endpoint) -> &Mutex<EndpointHealth> {
+        match endpoint {
+            Endpoint::Primary => &self.primary,
+            Endpoint::Alternate => &self.alternate
+        }
+    }
+
+    pub fn is_healthy(&self, endpoint: Endpoint) -> bool {
+        self.health(endpoint)
+            .lock()
+            .expect("Failover lock poisoned")
+            .is_healthy(Instant::now())
+    }
+    // Prefers the primary, falls back to the alternate while the primary is cooling down
+    // and goes back to the primary if both are unhealthy
+    pub fn select(&self) -> Endpoint {
+        if self.is_healthy(Endpoint::Primary) {
+            Endpoint::Primary
+        } else if self.is_healthy(Endpoint::Alternate) {
+            Endpoint::Alternate
+        } else {
+            Endpoint::Primary
+        }
+    }
+
+    pub fn record_success(&self, endpoint: Endpoint) {
+        let mut health = self.health(endpoint)
+            .lock()
+            .expect("Failover lock poisoned");
+
+        *health = EndpointHealth::default();
+    }
+
+    pub fn record_failure(&self, endpoint: Endpoint) {
+        let mut health = self.health(endpoint)
+            .lock()
+            .expect("Failover lock poisoned");
+
+        health.consecutive_failures += 1;
+
+        if health.consecutive_failures >= self.failure_threshold {
+            health.unhealthy_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::{ Arc, Mutex };
+use std::collections::VecDeque;
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+use tokio::task::JoinHandle;
+use crate::trading::executor::{ BuildRequest, Executor, ExecutorError };
+use crate::trading::market::ServerTime;
+/*
+
+Tracks the offset between the local clock and Bybit's clock using /v5/market/time.
+Signed requests are stamped with the corrected time so they stay inside the recv window
+
+*/
+
+// Number of recent samples the offset estimate is taken from
+const MAX_SAMPLES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockSample {
+    // server time - local time, positive when the local clock is behind
+    pub offset_ms: i64,
+    pub round_trip_ms: u64
+}
+
+impl ClockSample {
+    // Assumes the server stamped its time half way through the round trip
+    pub fn new(local_send_ms: u64, server_ms: u64, local_recv_ms: u64) -> ClockSample {
+        let round_trip_ms = local_recv_ms.saturating_sub(local_send_ms);
+        let local_mid_ms = local_send_ms + round_trip_ms / 2;
+
+        ClockSample {
+            offset_ms: server_ms as i64 - local_mid_ms as i64,
+            round_trip_ms
+        }
+    }
+    // One way latency estimate
+    pub fn latency_ms(&self) -> u64 {
+        self.round_trip_ms / 2
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TimeSync {
+    samples: Mutex<VecDeque<ClockSample>>
+}
+
+impl TimeSync {
+
+    pub fn new() -> TimeSync {
+        TimeSync::default()
+    }
+    // Stores a sample and returns the updated best estimate
+    pub fn record(&self, sample: ClockSample) -> ClockSample {
+        let mut samples = self.samples
+            .lock()
+            .expect("Time sync lock poisoned");
+
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+
+        samples.push_back(sample);
+
+        best_sample(&samples).expect("A sample was just recorded")
+    }
+    // The sample with the shortest round trip has the least uncertainty in its offset
+    pub fn estimate(&self) -> Option<ClockSample> {
+        let samples = self.samples
+            .lock()
+            .expect("Time sync lock poisoned");
+
+        best_sample(&samples)
+    }
+    // Zero until the first sync
+    pub fn offset_ms(&self) -> i64 {
+        self.estimate()
+            .map(|sample| sample.offset_ms)
+            .unwrap_or(0)
+    }
+
+    pub fn latency_ms(&self) -> Option<u64> {
+        self.estimate()
+            .map(|sample| sample.latency_ms())
+    }
+    // Local time corrected by the measured offset i.e. the exchange's clock
+    pub fn now_ms(&self) -> u128 {
+        (local_time_ms() as i128 + self.offset_ms() as i128).max(0) as u128
+    }
+}
+
+fn best_sample(samples: &VecDeque<ClockSample>) -> Option<ClockSample> {
+    samples
+        .iter()
+        .min_by_key(|sample| sample.round_trip_ms)
+        .copied()
+}
+
+// Returns the local unix time in milliseconds
+pub fn local_time_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is before unix epoch")
+        .as_millis()
+}
+
+impl Executor {
+    // Measures the clock offset against /v5/market/time and records it. The request is sent
+    // once without retries or rate limit waits so the round trip is a single attempt
+    pub async fn sync_time(&self) -> Result<ClockSample, ExecutorError> {
+        let local_send_ms = local_time_ms() as u64;
+
+        let server_time = self
+            .fetch_once::<ServerTime, _>(BuildRequest::get("/v5/market/time"))
+            .await?
+            .result;
+
+        let local_recv_ms = local_time_ms() as u64;
+
+        // timeNano carries the sub second precision timeSecond lacks
+        let server_ms = server_time.time_nano / 1_000_000;
+        let sample = ClockSample::new(local_send_ms, server_ms, local_recv_ms);
+
+        Ok(self.time_sync().record(sample))
+    }
+    // Re-syncs every interval in the background, failed syncs keep the previous estimate
+    pub fn spawn_time_sync(executor: Arc<Executor>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+
+            loop {
+                interval.tick().await;
+                let _ = executor.sync_time().await;
+            }
+        })
+    }
+}
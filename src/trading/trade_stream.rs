@@ -0,0 +1,253 @@
+use url::Url;
+use std::sync::{ Arc, Mutex };
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::{ Deserialize, Serialize, de::DeserializeOwned };
+use serde_json::{ json, Value };
+use tokio::sync::{ oneshot, watch };
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
+use crate::trading::executor::{ decode_envelope, ApiResponse, Executor, ExecutorError, DEFAULT_RECV_WINDOW };
+use crate::trading::websocket::{ self, Protocol, RealtimeAuth, StreamConfig, StreamEvent, StreamHandle };
+/*
+
+Bybit v5 WebSocket trade API (order.create, order.amend, order.cancel and their batch
+variants). Requests are correlated to responses by reqId and decoded exactly like REST
+
+*/
+
+pub const MAINNET_TRADE_URL: &str = "wss://stream.bybit.com/v5/trade";
+pub const TESTNET_TRADE_URL: &str = "wss://stream-testnet.bybit.com/v5/trade";
+
+// Which path the Executor's order entry methods use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OrderTransport {
+    #[default]
+    Rest,
+    WebSocket
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TradeConfig {
+    pub stream: StreamConfig,
+    // How long a request waits for its response before failing with ExecutorError::Timeout
+    pub request_timeout: Duration,
+    pub recv_window: u64
+}
+
+impl Default for TradeConfig {
+    fn default() -> TradeConfig {
+        TradeConfig {
+            stream: StreamConfig::default(),
+            request_timeout: Duration::from_secs(5),
+            recv_window: DEFAULT_RECV_WINDOW
+        }
+    }
+}
+
+// Responses echo the reqId of the request they answer
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeResponse {
+    #[serde(default)]
+    pub req_id: String,
+    pub ret_code: i64,
+    pub ret_msg: String,
+    pub op: String,
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default)]
+    pub ret_ext_info: Value,
+    #[serde(default)]
+    pub header: HashMap<String, String>
+}
+
+impl TradeResponse {
+    // Reshapes into the REST envelope so both transports decode the same way
+    pub fn into_envelope(self) -> ApiResponse<Value> {
+        let time = self.header
+            .get("Timenow")
+            .and_then(|time| time.parse().ok())
+            .unwrap_or(0);
+
+        ApiResponse {
+            ret_code: self.ret_code,
+            ret_msg: self.ret_msg,
+            result: self.data,
+            ret_ext_info: self.ret_ext_info,
+            time
+        }
+    }
+}
+
+type Reply = oneshot::Sender<Result<TradeResponse, ExecutorError>>;
+type Pending = Arc<Mutex<HashMap<String, Reply>>>;
+
+struct TradeProtocol {
+    auth: RealtimeAuth
+}
+
+impl Protocol for TradeProtocol {
+    type Event = TradeResponse;
+
+    fn auth_frame(&self) -> Option<String> {
+        Some(self.auth.fresh_auth_frame())
+    }
+
+    fn parse(&self, message: Value) -> Result<Option<TradeResponse>, serde_json::Error> {
+        serde_json::from_value(message).map(Some)
+    }
+}
+
+pub struct TradeSession {
+    stream: StreamHandle,
+    auth: RealtimeAuth,
+    config: TradeConfig,
+    pending: Pending,
+    connected: watch::Receiver<bool>,
+    next_req_id: AtomicU64,
+    dispatcher: JoinHandle<()>
+}
+
+impl TradeSession {
+    // Connects and authenticates in the background with the executor's credentials
+    pub fn connect(executor: &Executor, url: Url, config: TradeConfig) -> TradeSession {
+        let auth = executor.realtime_auth();
+        let protocol = TradeProtocol { auth: auth.clone() };
+
+        let (stream, events) = websocket::spawn(url, protocol, Vec::new(), config.stream);
+        let (connected_tx, connected) = watch::channel(false);
+        let pending = Pending::default();
+
+        let dispatcher = tokio::spawn(dispatch(events, pending.clone(), connected_tx));
+
+        TradeSession {
+            stream,
+            auth,
+            config,
+            pending,
+            connected,
+            next_req_id: AtomicU64::new(1),
+            dispatcher
+        }
+    }
+    // True once authenticated, false while reconnecting
+    pub fn is_connected(&self) -> bool {
+        *self.connected.borrow()
+    }
+    // Waits until the session is authenticated
+    pub async fn wait_connected(&self) -> Result<(), ExecutorError> {
+        let mut connected = self.connected.clone();
+
+        connected
+            .wait_for(|connected| *connected)
+            .await
+            .map(|_| ())
+            .map_err(|_| ExecutorError::Stream("trade session is closed".to_string()))
+    }
+    // Sends {"reqId", "header", "op", "args": [args]} and waits for the matching response
+    pub async fn request<R: DeserializeOwned, T: Serialize>(&self, op: &str, args: T) -> Result<ApiResponse<R>, ExecutorError> {
+        if !self.is_connected() {
+            return Err(ExecutorError::Stream("trade session is not connected".to_string()));
+        }
+
+        let args = serde_json::to_value(args)
+            .map_err(|e| ExecutorError::InvalidRequest(e.to_string()))?;
+
+        let req_id = self.next_req_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_string();
+
+        let frame = json!({
+            "reqId": req_id,
+            "header": {
+                "X-BAPI-TIMESTAMP": self.auth.now_ms().to_string(),
+                "X-BAPI-RECV-WINDOW": self.config.recv_window.to_string()
+            },
+            "op": op,
+            "args": [args]
+        });
+
+        let (reply, response) = oneshot::channel();
+
+        // Registered before sending so a disconnect can never strand the request
+        self.pending
+            .lock()
+            .expect("Trade session lock poisoned")
+            .insert(req_id.clone(), reply);
+
+        if self.stream.send(frame.to_string()).is_err() {
+            self.forget(&req_id);
+            return Err(ExecutorError::Stream("trade session is closed".to_string()));
+        }
+
+        let response = match tokio::time::timeout(self.config.request_timeout, response).await {
+            Ok(Ok(response)) => response?,
+            Ok(Err(_)) => return Err(ExecutorError::Stream("trade session is closed".to_string())),
+            Err(_) => {
+                self.forget(&req_id);
+                return Err(ExecutorError::Timeout);
+            }
+        };
+
+        decode_envelope(response.into_envelope())
+    }
+    // Closes the connection, requests still in flight fail with ExecutorError::Stream
+    pub async fn close(self) {
+        self.stream.close().await;
+        let _ = self.dispatcher.await;
+    }
+
+    fn forget(&self, req_id: &str) {
+        self.pending
+            .lock()
+            .expect("Trade session lock poisoned")
+            .remove(req_id);
+    }
+}
+
+// Routes responses to their waiting request and fails everything in flight on disconnect
+async fn dispatch(mut events: UnboundedReceiver<StreamEvent<TradeResponse>>, pending: Pending, connected: watch::Sender<bool>) {
+    while let Some(event) = events.recv().await {
+        match event {
+            StreamEvent::Connected => {
+                let _ = connected.send(true);
+            }
+
+            StreamEvent::Disconnected => {
+                let _ = connected.send(false);
+                fail_pending(&pending, "connection lost, order state unknown");
+            }
+
+            StreamEvent::Message(response) => {
+                let reply = pending
+                    .lock()
+                    .expect("Trade session lock poisoned")
+                    .remove(&response.req_id);
+
+                if let Some(reply) = reply {
+                    let _ = reply.send(Ok(response));
+                }
+            }
+
+            StreamEvent::Error(_) => {}
+        }
+    }
+
+    let _ = connected.send(false);
+    fail_pending(&pending, "trade session is closed");
+}
+
+fn fail_pending(pending: &Pending, msg: &str) {
+    let replies: Vec<Reply> = pending
+        .lock()
+        .expect("Trade session lock poisoned")
+        .drain()
+        .map(|(_, reply)| reply)
+        .collect();
+
+    for reply in replies {
+        let _ = reply.send(Err(ExecutorError::Stream(msg.to_string())));
+    }
+}
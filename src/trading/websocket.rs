@@ -0,0 +1,395 @@
+use std::fmt;
+use url::Url;
+use std::sync::Arc;
+use hmac::{ Hmac, Mac };
+use sha2::Sha256;
+use serde_json::{ json, Value };
+use std::time::{ Duration, Instant };
+use futures_util::{ SinkExt, StreamExt };
+use tokio::task::JoinHandle;
+use tokio::sync::mpsc::{ self, UnboundedReceiver, UnboundedSender };
+use tokio_tungstenite::{ connect_async, tungstenite::Message };
+use crate::trading::timesync::TimeSync;
+/*
+
+Shared Bybit v5 WebSocket plumbing used by the public, private and trade streams. A stream runs
+in its own task, keeps its subscriptions, answers heartbeats and reconnects + resubscribes
+whenever the connection drops
+
+*/
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Bybit accepts at most 10 topics per subscribe request on spot
+const MAX_TOPICS_PER_REQUEST: usize = 10;
+
+// How long an auth signature stays valid for
+const AUTH_EXPIRY_MS: u128 = 10_000;
+
+// Credentials and the exchange clock, shared by the private and trade streams
+#[derive(Clone, Debug)]
+pub struct RealtimeAuth {
+    api_key: String,
+    api_secret: String,
+    time_sync: Arc<TimeSync>
+}
+
+impl RealtimeAuth {
+
+    pub fn new(api_key: String, api_secret: String, time_sync: Arc<TimeSync>) -> RealtimeAuth {
+        RealtimeAuth { api_key, api_secret, time_sync }
+    }
+
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+    // Exchange time in ms, see TimeSync::now_ms
+    pub fn now_ms(&self) -> u128 {
+        self.time_sync.now_ms()
+    }
+    // Signs "GET/realtime" + expires with HMAC-SHA256
+    pub fn sign(&self, expires: u128) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.api_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+
+        mac.update(b"GET/realtime");
+        mac.update(expires.to_string().as_bytes());
+
+        hex::encode(mac.finalize().into_bytes())
+    }
+    // Builds {"op": "auth", "args": [api_key, expires, signature]}
+    pub fn auth_frame(&self, expires: u128) -> String {
+        json!({ "op": "auth", "args": [self.api_key, expires as u64, self.sign(expires)] }).to_string()
+    }
+    // Auth frame that expires shortly, built fresh for every (re)connect
+    pub fn fresh_auth_frame(&self) -> String {
+        self.auth_frame(self.now_ms() + AUTH_EXPIRY_MS)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StreamConfig {
+    // Bybit drops connections that have not pinged for 30s
+    pub ping_interval: Duration,
+    // The connection is considered dead if no pong arrives within this window
+    pub pong_timeout: Duration,
+    pub reconnect_delay: Duration,
+    pub auth_timeout: Duration
+}
+
+impl Default for StreamConfig {
+    fn default() -> StreamConfig {
+        StreamConfig {
+            ping_interval: Duration::from_secs(20),
+            pong_timeout: Duration::from_secs(45),
+            reconnect_delay: Duration::from_secs(1),
+            auth_timeout: Duration::from_secs(10)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent<E> {
+    Connected, // Fired after every (re)connect once subscriptions have been sent
+    Disconnected, // The connection dropped, a reconnect follows
+    Error(String), // Connect, auth or subscription failures reported by the stream
+    Message(E)
+}
+
+// Returned when commanding a stream whose task has already shut down
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamClosed;
+
+impl fmt::Display for StreamClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stream is closed")
+    }
+}
+
+impl std::error::Error for StreamClosed {}
+
+#[derive(Debug)]
+enum Command {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    Send(String),
+    Close
+}
+
+// Controls a running stream, dropping the handle also closes the stream
+#[derive(Debug)]
+pub struct StreamHandle {
+    commands: UnboundedSender<Command>,
+    task: JoinHandle<()>
+}
+
+impl StreamHandle {
+
+    pub fn subscribe<T: ToString>(&self, topics: &[T]) -> Result<(), StreamClosed> {
+        let topics = topics.iter().map(ToString::to_string).collect();
+
+        self.commands
+            .send(Command::Subscribe(topics))
+            .map_err(|_| StreamClosed)
+    }
+
+    pub fn unsubscribe<T: ToString>(&self, topics: &[T]) -> Result<(), StreamClosed> {
+        let topics = topics.iter().map(ToString::to_string).collect();
+
+        self.commands
+            .send(Command::Unsubscribe(topics))
+            .map_err(|_| StreamClosed)
+    }
+    // Sends a raw frame, frames sent while disconnected are dropped
+    pub fn send(&self, frame: String) -> Result<(), StreamClosed> {
+        self.commands
+            .send(Command::Send(frame))
+            .map_err(|_| StreamClosed)
+    }
+    // Closes the connection and waits for the stream task to finish
+    pub async fn close(self) {
+        let _ = self.commands.send(Command::Close);
+        let _ = self.task.await;
+    }
+}
+
+// What differs between streams: how to authenticate and how to decode data messages
+pub trait Protocol: Send + Sync + 'static {
+    type Event: Send + 'static;
+    // Auth frame sent and acknowledged before subscribing, None for public streams
+    fn auth_frame(&self) -> Option<String> {
+        None
+    }
+    // Decodes a data message, ping, subscribe and auth responses never reach here.
+    // Ok(None) skips messages the protocol does not handle
+    fn parse(&self, message: Value) -> Result<Option<Self::Event>, serde_json::Error>;
+}
+
+// Spawns the stream task, requires a running tokio runtime
+pub fn spawn<P: Protocol>(url: Url, protocol: P, topics: Vec<String>, config: StreamConfig) -> (StreamHandle, UnboundedReceiver<StreamEvent<P::Event>>) {
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    let task = tokio::spawn(run(url, protocol, topics, config, command_rx, event_tx));
+
+    (StreamHandle { commands: command_tx, task }, event_rx)
+}
+
+enum SessionEnd {
+    Dropped, // Reconnect
+    Closed // Closed by the handle or the event receiver went away
+}
+
+async fn run<P: Protocol>(url: Url, protocol: P, mut topics: Vec<String>, config: StreamConfig, mut commands: UnboundedReceiver<Command>, events: UnboundedSender<StreamEvent<P::Event>>) {
+    loop {
+        match session(&url, &protocol, &mut topics, &config, &mut commands, &events).await {
+            SessionEnd::Closed => return,
+            SessionEnd::Dropped => {
+                if events.send(StreamEvent::Disconnected).is_err() {
+                    return;
+                }
+            }
+        }
+
+        // Keep serving commands while disconnected so close() never waits on a dead endpoint
+        let reconnect = tokio::time::sleep(config.reconnect_delay);
+        tokio::pin!(reconnect);
+
+        loop {
+            tokio::select! {
+                _ = &mut reconnect => break,
+
+                command = commands.recv() => match command {
+                    Some(Command::Subscribe(new)) => {
+                        for topic in new {
+                            if !topics.contains(&topic) {
+                                topics.push(topic);
+                            }
+                        }
+                    }
+
+                    Some(Command::Unsubscribe(old)) => topics.retain(|topic| !old.contains(topic)),
+
+                    Some(Command::Send(_)) => {}
+
+                    Some(Command::Close) | None => return
+                }
+            }
+        }
+    }
+}
+
+async fn session<P: Protocol>(url: &Url, protocol: &P, topics: &mut Vec<String>, config: &StreamConfig, commands: &mut UnboundedReceiver<Command>, events: &UnboundedSender<StreamEvent<P::Event>>) -> SessionEnd {
+    let (mut write, mut read) = match connect_async(url.as_str()).await {
+        Ok((ws, _)) => ws.split(),
+        Err(e) => {
+            let _ = events.send(StreamEvent::Error(format!("connect failed: {}", e)));
+            return SessionEnd::Dropped;
+        }
+    };
+
+    if let Some(auth) = protocol.auth_frame() {
+        if write.send(Message::Text(auth)).await.is_err() {
+            return SessionEnd::Dropped;
+        }
+
+        let ack = tokio::time::timeout(config.auth_timeout, async {
+            while let Some(Ok(message)) = read.next().await {
+                let Message::Text(text) = message else { continue };
+                let Ok(value) = serde_json::from_str::<Value>(&text) else { continue };
+
+                if value.get("op").and_then(Value::as_str) == Some("auth") {
+                    return Some(value);
+                }
+            }
+
+            None
+        })
+        .await;
+
+        match ack {
+            Ok(Some(value)) if is_success(&value) => {}
+            Ok(Some(value)) => {
+                let _ = events.send(StreamEvent::Error(format!("auth failed: {}", ret_msg(&value))));
+                return SessionEnd::Dropped;
+            }
+            _ => {
+                let _ = events.send(StreamEvent::Error("auth timed out".to_string()));
+                return SessionEnd::Dropped;
+            }
+        }
+    }
+
+    for chunk in topics.chunks(MAX_TOPICS_PER_REQUEST) {
+        if write.send(Message::Text(op_frame("subscribe", chunk))).await.is_err() {
+            return SessionEnd::Dropped;
+        }
+    }
+
+    if events.send(StreamEvent::Connected).is_err() {
+        return SessionEnd::Closed;
+    }
+
+    let mut ping = tokio::time::interval(config.ping_interval);
+    let mut last_pong = Instant::now();
+
+    // The first tick completes straight away
+    ping.tick().await;
+
+    loop {
+        tokio::select! {
+            message = read.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Ping(data))) => {
+                        if write.send(Message::Pong(data)).await.is_err() {
+                            return SessionEnd::Dropped;
+                        }
+                        continue;
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return SessionEnd::Dropped,
+                    Some(Ok(_)) => continue
+                };
+
+                let Ok(value) = serde_json::from_str::<Value>(&text) else { continue };
+
+                match value.get("op").and_then(Value::as_str) {
+                    // Public streams answer with op "ping" + ret_msg "pong", private ones with op "pong"
+                    Some("ping") | Some("pong") => last_pong = Instant::now(),
+
+                    Some("subscribe") | Some("unsubscribe") | Some("auth") => {
+                        if !is_success(&value) && events.send(StreamEvent::Error(ret_msg(&value))).is_err() {
+                            return SessionEnd::Closed;
+                        }
+                    }
+
+                    // Data messages and responses to other ops e.g. order.create
+                    _ => {
+                        let event = match protocol.parse(value) {
+                            Ok(Some(event)) => StreamEvent::Message(event),
+                            Ok(None) => continue,
+                            Err(e) => StreamEvent::Error(format!("decode failed: {}", e))
+                        };
+
+                        if events.send(event).is_err() {
+                            return SessionEnd::Closed;
+                        }
+                    }
+                }
+            }
+
+            command = commands.recv() => {
+                let frame = match command {
+                    Some(Command::Subscribe(new)) => {
+                        let new: Vec<String> = new
+                            .into_iter()
+                            .filter(|topic| !topics.contains(topic))
+                            .collect();
+
+                        if new.is_empty() {
+                            continue;
+                        }
+
+                        topics.extend(new.iter().cloned());
+                        op_frame("subscribe", &new)
+                    }
+
+                    Some(Command::Unsubscribe(old)) => {
+                        topics.retain(|topic| !old.contains(topic));
+                        op_frame("unsubscribe", &old)
+                    }
+
+                    Some(Command::Send(frame)) => frame,
+
+                    Some(Command::Close) | None => {
+                        let _ = write.send(Message::Close(None)).await;
+                        return SessionEnd::Closed;
+                    }
+                };
+
+                if write.send(Message::Text(frame)).await.is_err() {
+                    return SessionEnd::Dropped;
+                }
+            }
+
+            _ = ping.tick() => {
+                if last_pong.elapsed() > config.pong_timeout {
+                    return SessionEnd::Dropped;
+                }
+
+                if write.send(Message::Text(json!({ "op": "ping" }).to_string())).await.is_err() {
+                    return SessionEnd::Dropped;
+                }
+            }
+        }
+    }
+}
+
+// Builds {"op": op, "args": [...]}
+pub fn op_frame<T: serde::Serialize>(op: &str, args: &[T]) -> String {
+    json!({ "op": op, "args": args }).to_string()
+}
+
+// Stream endpoints answer with "success", the trade endpoint with "retCode"
+fn is_success(value: &Value) -> bool {
+    let success = value
+        .get("success")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+
+    let ret_code = value
+        .get("retCode")
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+
+    success && ret_code == 0
+}
+
+fn ret_msg(value: &Value) -> String {
+    value
+        .get("ret_msg")
+        .or_else(|| value.get("retMsg"))
+        .and_then(Value::as_str)
+        .unwrap_or("unknown error")
+        .to_string()
+}
@@ -1,74 +1,173 @@
 use url::Url;
-use serde_json::Value;
-use tokio::runtime::Runtime;
+use std::time::Duration;
 use std::collections::HashMap;
-use reqwest::{ Client, header::HeaderMap };
-
-pub struct BuildRequest<'a, T> {
-    method: &'a str,
-    url: &'a str,
-    // Payload should be [[str, str]] for "GET"
-    // Payload should be HashMap for "POST"
-    payload: T, 
-    headers: HeaderMap
-}
+use reqwest::header::{ HeaderName, HeaderValue };
+use serde::Deserialize;
+use rust_workshop::executor::{
+    decode_response, ret_code, ApiResponse, BuildRequest, Executor, ExecutorError, Method, Payload, RequestOptions, DEFAULT_RECV_WINDOW
+};
 
-pub struct Executor<'a> {
-    api_key: &'a str,
-    api_secret: &'a str, 
-    https_endpoint: Url,
-    https_alt_endpoint: Url,
-    client: Client
+#[derive(Debug, PartialEq, Deserialize)]
+struct ServerTime {
+    #[serde(rename = "timeSecond")]
+    time_second: String
 }
 
-impl Executor<'_> {
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_executor() {
+        let executor = Executor::new(
+            "key".to_string(),
+            "secret".to_string(),
+            Url::parse("https://api.bybit.com").unwrap(),
+            Url::parse("https://api.bytick.com").unwrap()
+        );
+
+        assert_eq!(executor.api_key(), "key");
+        assert_eq!(executor.https_endpoint().as_str(), "https://api.bybit.com/");
+        assert_eq!(executor.https_alt_endpoint().as_str(), "https://api.bytick.com/");
+    }
+
+    #[test]
+    fn test_build_request_executor() {
+        let params = [
+            ["category", "linear"],
+            ["symbol", "BTCUSDT"]
+        ];
+
+        let build_request = BuildRequest::get("/v5/market/tickers")
+            .query(params);
+
+        assert_eq!(build_request.method, Method::Get);
+        assert_eq!(build_request.path, "/v5/market/tickers");
+        assert_eq!(build_request.payload, Payload::Query(params));
+        assert_eq!(build_request.options, RequestOptions::default());
+    }
+
+    #[test]
+    fn test_build_request_options_executor() {
+        let body = HashMap::from([("category", "linear"), ("symbol", "BTCUSDT")]);
 
-    pub fn new<'a> (api_key: &'a str, api_secret: &'a str, https_endpoint: Url, https_alt_endpoint: Url) -> Executor<'a> {
-        Executor {
-            api_key: api_key,
-            api_secret: api_secret,
-            https_endpoint: https_endpoint,
-            https_alt_endpoint: https_alt_endpoint,
-            client: Client::new()
-        }
+        let build_request = BuildRequest::post("/v5/order/create")
+            .body(body.clone())
+            .private()
+            .timeout(Duration::from_secs(2))
+            .recv_window(10_000)
+            .header(HeaderName::from_static("referer"), HeaderValue::from_static("workshop"));
+
+        assert_eq!(build_request.method, Method::Post);
+        assert_eq!(build_request.payload, Payload::Body(body));
+        assert!(build_request.options.private);
+        assert_eq!(build_request.options.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(build_request.options.recv_window, 10_000);
+        assert_eq!(build_request.headers["referer"], "workshop");
+    }
+
+    #[test]
+    fn test_method_conversion_executor() {
+        assert_eq!(reqwest::Method::from(Method::Get), reqwest::Method::GET);
+        assert_eq!(reqwest::Method::from(Method::Post), reqwest::Method::POST);
+        assert_eq!(reqwest::Method::from(Method::Put), reqwest::Method::PUT);
+        assert_eq!(reqwest::Method::from(Method::Delete), reqwest::Method::DELETE);
+    }
+
+    #[test]
+    fn test_sign_get_executor() {
+        let executor = Executor::new(
+            "key".to_string(),
+            "secret".to_string(),
+            Url::parse("https://api.bybit.com").unwrap(),
+            Url::parse("https://api.bytick.com").unwrap()
+        );
+
+        let signature = executor.sign(1658384314791, 5000, "category=linear&symbol=BTCUSDT");
+
+        assert_eq!(signature, "ccfb83a4990aaab72c1049cfd1dcf888f713c1fb105568d4526c6ced2f2973d8");
     }
 
-    pub async fn fetch<'a, T: serde::ser::Serialize> (&self, build_request: BuildRequest<'a, T>) -> Result<Value, reqwest::Error> {
-        match  build_request.method {
-
-            "Get" => {
-                let resp = self
-                    .client
-                    .get(build_request.url)
-                    .query(&build_request.payload)
-                    .headers(build_request.headers)
-                    .send()
-                    .await?;
-
-                let resp_object = resp
-                    .json::<Value>()
-                    .await?;
-
-                Ok(resp_object)
-            }
-
-            "Post" => {
-                let resp = self
-                    .client
-                    .post(build_request.url)
-                    .json(&build_request.payload)
-                    .headers(build_request.headers)
-                    .send()
-                    .await?;
-
-                let resp_object = resp 
-                    .json::<Value>()
-                    .await?;
-
-                Ok(resp_object)
-            }
-            
-            _ => todo!()
-        }
+    #[test]
+    fn test_sign_post_executor() {
+        let executor = Executor::new(
+            "key".to_string(),
+            "secret".to_string(),
+            Url::parse("https://api.bybit.com").unwrap(),
+            Url::parse("https://api.bytick.com").unwrap()
+        );
+
+        let signature = executor.sign(1658384314791, 5000, "{\"category\":\"linear\"}");
+
+        assert_eq!(signature, "7257f30b3296c3c86ec7e7297ca17c478759c83787e102a1c62a7d6ae8380b5d");
+    }
+
+    #[test]
+    fn test_auth_headers_executor() {
+        let executor = Executor::new(
+            "key".to_string(),
+            "secret".to_string(),
+            Url::parse("https://api.bybit.com").unwrap(),
+            Url::parse("https://api.bytick.com").unwrap()
+        );
+
+        let headers = executor.auth_headers(1658384314791, DEFAULT_RECV_WINDOW, "category=linear&symbol=BTCUSDT");
+
+        assert_eq!(headers["X-BAPI-API-KEY"], "key");
+        assert_eq!(headers["X-BAPI-TIMESTAMP"], "1658384314791");
+        assert_eq!(headers["X-BAPI-RECV-WINDOW"], "5000");
+        assert_eq!(headers["X-BAPI-SIGN"], "ccfb83a4990aaab72c1049cfd1dcf888f713c1fb105568d4526c6ced2f2973d8");
+    }
+
+    #[test]
+    fn test_decode_response_ok_executor() {
+        let body = r#"{"retCode":0,"retMsg":"OK","result":{"timeSecond":"1688639403"},"retExtInfo":{},"time":1688639403423}"#;
+
+        let response: ApiResponse<ServerTime> = decode_response(200, body).unwrap();
+
+        assert_eq!(response.ret_code, 0);
+        assert_eq!(response.ret_msg, "OK");
+        assert_eq!(response.result, ServerTime { time_second: "1688639403".to_string() });
+        assert_eq!(response.time, 1688639403423);
+    }
+
+    #[test]
+    fn test_decode_response_exchange_error_executor() {
+        let body = r#"{"retCode":110007,"retMsg":"ab not enough for new order","result":{},"retExtInfo":{},"time":1688639403423}"#;
+
+        let error = decode_response::<ServerTime>(200, body).unwrap_err();
+
+        assert_eq!(error.code(), Some(ret_code::INSUFFICIENT_BALANCE));
+        assert!(matches!(error, ExecutorError::Exchange { .. }));
+    }
+
+    #[test]
+    fn test_decode_response_rate_limited_executor() {
+        let body = r#"{"retCode":10006,"retMsg":"Too many visits!","result":{},"retExtInfo":{},"time":1688639403423}"#;
+
+        let error = decode_response::<ServerTime>(200, body).unwrap_err();
+        assert!(error.is_rate_limited());
+        assert_eq!(error.code(), Some(ret_code::TOO_MANY_VISITS));
+
+        let error = decode_response::<ServerTime>(403, "access too frequent").unwrap_err();
+        assert!(error.is_rate_limited());
+        assert_eq!(error.code(), None);
+    }
+
+    #[test]
+    fn test_decode_response_status_and_decode_errors_executor() {
+        let error = decode_response::<ServerTime>(502, "Bad Gateway").unwrap_err();
+        assert!(matches!(error, ExecutorError::Status { status: 502, .. }));
+
+        let error = decode_response::<ServerTime>(200, "not json").unwrap_err();
+        assert!(matches!(error, ExecutorError::Decode(_)));
+
+        let body = r#"{"retCode":0,"retMsg":"OK","result":{"unexpected":1},"retExtInfo":{},"time":1}"#;
+        let error = decode_response::<ServerTime>(200, body).unwrap_err();
+        assert!(matches!(error, ExecutorError::Decode(_)));
     }
 }
@@ -0,0 +1,55 @@
+use rust_workshop::executor::{ decode_response, ApiResponse };
+use rust_workshop::fees::{ FeeRate, FeeRates, FeeSchedule };
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_schedule_fees() {
+        let schedule = FeeSchedule::new(FeeRate::new(0.001, 0.001))
+            .with_rate("BTCUSDT", FeeRate::new(-0.000125, 0.00055));
+
+        assert_eq!(schedule.rate("BTCUSDT"), FeeRate::new(-0.000125, 0.00055));
+        assert_eq!(schedule.rate("ETHUSDT"), schedule.default_rate());
+
+        // Maker rebates come back negative
+        assert_eq!(schedule.fee("BTCUSDT", 2.0, 40000.0, true), -10.0);
+        assert_eq!(schedule.fee("BTCUSDT", 2.0, 40000.0, false), 44.0);
+        assert_eq!(schedule.fee("ETHUSDT", -1.0, 2000.0, false), 2.0);
+
+        assert_eq!(FeeSchedule::default().fee("BTCUSDT", 1.0, 40000.0, false), 0.0);
+    }
+
+    #[test]
+    fn test_decode_fee_rates_fees() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "list": [
+                    { "symbol": "BTCUSDT", "takerFeeRate": "0.00055", "makerFeeRate": "-0.000125" },
+                    { "symbol": "", "baseCoin": "SOL", "takerFeeRate": "0.0003", "makerFeeRate": "0.0003" }
+                ]
+            },
+            "retExtInfo": {},
+            "time": 1676360412576
+        }"#;
+
+        let response: ApiResponse<FeeRates> = decode_response(200, body).unwrap();
+        let fee_rates = &response.result.list;
+
+        assert_eq!(fee_rates[0].symbol, "BTCUSDT");
+        assert_eq!(fee_rates[0].maker_fee_rate, -0.000125);
+        assert_eq!(fee_rates[1].base_coin, "SOL");
+
+        let schedule = FeeSchedule::from_fee_rates(FeeRate::new(0.0002, 0.00055), &fee_rates[..1]);
+
+        assert_eq!(schedule.rate("BTCUSDT").maker, -0.000125);
+        assert_eq!(schedule.rate("ETHUSDT").maker, 0.0002);
+    }
+}
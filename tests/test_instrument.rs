@@ -0,0 +1,86 @@
+use serde_json::json;
+use rust_workshop::instrument::{ InstrumentSpec, Price, Qty, Rounding };
+use rust_workshop::market::Instrument;
+
+fn linear_instrument() -> Instrument {
+    serde_json::from_value(json!({
+        "symbol": "BTCUSDT",
+        "status": "Trading",
+        "baseCoin": "BTC",
+        "quoteCoin": "USDT",
+        "priceFilter": { "minPrice": "0.10", "maxPrice": "199999.80", "tickSize": "0.10" },
+        "lotSizeFilter": { "maxOrderQty": "100.000", "minOrderQty": "0.005", "qtyStep": "0.001" }
+    })).unwrap()
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_instrument_instrument() {
+        let spec = InstrumentSpec::from_instrument(&linear_instrument()).unwrap();
+
+        assert_eq!(spec.symbol, "BTCUSDT");
+        assert_eq!(spec.tick_size(), 0.1);
+        assert_eq!(spec.lot_size(), 0.001);
+        assert_eq!(spec.min_qty(), Qty(5));
+
+        assert_eq!(spec.format_price(Price(300001)), "30000.1");
+        assert_eq!(spec.format_qty(Qty(10)), "0.010");
+    }
+
+    #[test]
+    fn test_rounding_instrument() {
+        let spec = InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap();
+
+        // 0.1 + 0.2 and 0.3 are different floats but the same tick
+        assert_eq!(spec.price(0.1 + 0.2, Rounding::Nearest), Some(Price(3)));
+        assert_eq!(spec.price(0.3, Rounding::Down), Some(Price(3)));
+        assert_eq!(spec.price(0.3, Rounding::Up), Some(Price(3)));
+        assert_eq!(spec.round_price(0.1 + 0.2, Rounding::Nearest), Some(0.3));
+
+        assert_eq!(spec.bid_price(30000.15), Some(Price(300001)));
+        assert_eq!(spec.ask_price(30000.11), Some(Price(300002)));
+        assert_eq!(spec.bid_price(30000.15).unwrap() + 1, Price(300002));
+
+        assert_eq!(spec.qty(0.0129, Rounding::Down), Some(Qty(12)));
+        assert_eq!(spec.qty_value(Qty(12)), 0.012);
+    }
+
+    #[test]
+    fn test_order_qty_instrument() {
+        let spec = InstrumentSpec::from_instrument(&linear_instrument()).unwrap();
+
+        assert_eq!(spec.order_qty(0.0049), None);
+        assert_eq!(spec.order_qty(0.005), Some(Qty(5)));
+        assert_eq!(spec.order_qty(0.0123), Some(Qty(12)));
+    }
+
+    #[test]
+    fn test_steps_instrument() {
+        assert_eq!(InstrumentSpec::new("X", 0.0, 1.0), None);
+        assert_eq!(InstrumentSpec::new("X", 0.1, f64::NAN), None);
+
+        let spec = InstrumentSpec::new("X", 0.5, 10.0).unwrap();
+
+        assert_eq!(spec.price(1.26, Rounding::Nearest), Some(Price(3)));
+        assert_eq!(spec.format_price(Price(3)), "1.5");
+        assert_eq!(spec.format_qty(Qty(3)), "30");
+
+        // Values that don't fit an i64 of steps are rejected instead of saturating
+        assert_eq!(spec.price(f64::NAN, Rounding::Nearest), None);
+        assert_eq!(spec.price(f64::INFINITY, Rounding::Up), None);
+        assert_eq!(spec.qty(-1e20, Rounding::Down), None);
+        assert_eq!(spec.price_value(Price(i64::MAX)), i64::MAX as f64 * 0.5);
+
+        let fallback = InstrumentSpec::fallback("X");
+
+        assert_eq!(fallback.qty(2e11, Rounding::Nearest), None);
+        assert_eq!(fallback.qty(9e10, Rounding::Nearest), Some(Qty(9_000_000_000_000_000_000)));
+    }
+}
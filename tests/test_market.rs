@@ -0,0 +1,266 @@
+use rust_workshop::executor::{ decode_response, ApiResponse };
+use rust_workshop::market::{
+    Category, Instrument, InstrumentsInfo, Interval, Kline, Klines, Level, OrderbookSnapshot, RecentTrades, Side, Tickers
+};
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_and_interval_market() {
+        assert_eq!(Category::Linear.as_str(), "linear");
+        assert_eq!(serde_json::to_string(&Category::Spot).unwrap(), "\"spot\"");
+        assert_eq!(Interval::Hour4.as_str(), "240");
+        assert_eq!(serde_json::to_string(&Interval::Day).unwrap(), "\"D\"");
+        assert_eq!(serde_urlencoded::to_string([("interval", Interval::Min15)]).unwrap(), "interval=15");
+    }
+
+    #[test]
+    fn test_decode_klines_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "symbol": "BTCUSD",
+                "category": "inverse",
+                "list": [
+                    ["1670608800000", "17071", "17073", "17027", "17055.5", "268611", "15.74462667"],
+                    ["1670605200000", "17071.5", "17071.5", "17061", "17071", "4177", "0.24469757"]
+                ]
+            },
+            "retExtInfo": {},
+            "time": 1672025956592
+        }"#;
+
+        let response: ApiResponse<Klines> = decode_response(200, body).unwrap();
+        let klines = response.result;
+
+        assert_eq!(klines.category, Category::Inverse);
+        assert_eq!(klines.list.len(), 2);
+        assert_eq!(klines.list[0], Kline {
+            start_time: 1670608800000,
+            open: 17071.0,
+            high: 17073.0,
+            low: 17027.0,
+            close: 17055.5,
+            volume: 268611.0,
+            turnover: 15.74462667
+        });
+    }
+
+    #[test]
+    fn test_decode_tickers_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "category": "linear",
+                "list": [{
+                    "symbol": "BTCUSDT",
+                    "lastPrice": "16597.00",
+                    "indexPrice": "16598.54",
+                    "markPrice": "16596.00",
+                    "prevPrice24h": "16464.50",
+                    "price24hPcnt": "0.008047",
+                    "highPrice24h": "30912.50",
+                    "lowPrice24h": "15700.00",
+                    "prevPrice1h": "16595.50",
+                    "openInterest": "373504107",
+                    "openInterestValue": "6198.75",
+                    "turnover24h": "2352.94950046",
+                    "volume24h": "49337318",
+                    "fundingRate": "-0.001034",
+                    "nextFundingTime": "1672387200000",
+                    "predictedDeliveryPrice": "",
+                    "basisRate": "",
+                    "deliveryFeeRate": "",
+                    "deliveryTime": "0",
+                    "ask1Size": "1",
+                    "bid1Price": "16596.00",
+                    "ask1Price": "16597.50",
+                    "bid1Size": "1"
+                }]
+            },
+            "retExtInfo": {},
+            "time": 1672376496682
+        }"#;
+
+        let response: ApiResponse<Tickers> = decode_response(200, body).unwrap();
+        let ticker = &response.result.list[0];
+
+        assert_eq!(ticker.symbol, "BTCUSDT");
+        assert_eq!(ticker.last_price, 16597.0);
+        assert_eq!(ticker.bid1_price, 16596.0);
+        assert_eq!(ticker.ask1_price, 16597.5);
+        assert_eq!(ticker.funding_rate, Some(-0.001034));
+        assert_eq!(ticker.next_funding_time, Some(1672387200000));
+    }
+
+    #[test]
+    fn test_decode_spot_tickers_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "category": "spot",
+                "list": [{
+                    "symbol": "BTCUSDT",
+                    "bid1Price": "20517.96",
+                    "bid1Size": "2",
+                    "ask1Price": "20527.77",
+                    "ask1Size": "1.862172",
+                    "lastPrice": "20533.13",
+                    "prevPrice24h": "20393.48",
+                    "price24hPcnt": "0.0068",
+                    "highPrice24h": "21128.12",
+                    "lowPrice24h": "20318.89",
+                    "turnover24h": "243765620.65899866",
+                    "volume24h": "11801.27771",
+                    "usdIndexPrice": "20784.12009279"
+                }]
+            },
+            "retExtInfo": {},
+            "time": 1673859087947
+        }"#;
+
+        let response: ApiResponse<Tickers> = decode_response(200, body).unwrap();
+        let ticker = &response.result.list[0];
+
+        assert_eq!(ticker.ask1_size, 1.862172);
+        assert_eq!(ticker.mark_price, None);
+        assert_eq!(ticker.funding_rate, None);
+    }
+
+    #[test]
+    fn test_decode_orderbook_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "s": "BTCUSDT",
+                "a": [["16638.64", "0.008479"], ["16639.00", "1.2"]],
+                "b": [["16638.27", "0.305749"]],
+                "ts": 1672765737733,
+                "u": 5277055,
+                "seq": 7961638724
+            },
+            "retExtInfo": {},
+            "time": 1672765737734
+        }"#;
+
+        let response: ApiResponse<OrderbookSnapshot> = decode_response(200, body).unwrap();
+        let snapshot = response.result;
+
+        assert_eq!(snapshot.symbol, "BTCUSDT");
+        assert_eq!(snapshot.asks, vec![Level { price: 16638.64, size: 0.008479 }, Level { price: 16639.0, size: 1.2 }]);
+        assert_eq!(snapshot.bids, vec![Level { price: 16638.27, size: 0.305749 }]);
+        assert_eq!(snapshot.update_id, 5277055);
+        assert_eq!(snapshot.seq, 7961638724);
+    }
+
+    #[test]
+    fn test_decode_recent_trades_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "category": "spot",
+                "list": [{
+                    "execId": "2100000000007764263",
+                    "symbol": "BTCUSDT",
+                    "price": "16618.49",
+                    "size": "0.00012",
+                    "side": "Buy",
+                    "time": "1672052955758",
+                    "isBlockTrade": false
+                }]
+            },
+            "retExtInfo": {},
+            "time": 1672053054358
+        }"#;
+
+        let response: ApiResponse<RecentTrades> = decode_response(200, body).unwrap();
+        let trade = &response.result.list[0];
+
+        assert_eq!(trade.side, Side::Buy);
+        assert_eq!(trade.price, 16618.49);
+        assert_eq!(trade.size, 0.00012);
+        assert_eq!(trade.time, 1672052955758);
+    }
+
+    #[test]
+    fn test_decode_instruments_info_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "category": "linear",
+                "list": [{
+                    "symbol": "BTCUSDT",
+                    "contractType": "LinearPerpetual",
+                    "status": "Trading",
+                    "baseCoin": "BTC",
+                    "quoteCoin": "USDT",
+                    "launchTime": "1585526400000",
+                    "priceScale": "2",
+                    "priceFilter": { "minPrice": "0.10", "maxPrice": "199999.80", "tickSize": "0.10" },
+                    "lotSizeFilter": { "maxOrderQty": "100.000", "minOrderQty": "0.001", "qtyStep": "0.001" }
+                }],
+                "nextPageCursor": ""
+            },
+            "retExtInfo": {},
+            "time": 1672712495660
+        }"#;
+
+        let response: ApiResponse<InstrumentsInfo> = decode_response(200, body).unwrap();
+        let instrument: &Instrument = &response.result.list[0];
+
+        assert_eq!(instrument.symbol, "BTCUSDT");
+        assert_eq!(instrument.price_filter.tick_size, 0.1);
+        assert_eq!(instrument.price_filter.min_price, Some(0.1));
+        assert_eq!(instrument.lot_size_filter.min_order_qty, 0.001);
+        assert_eq!(instrument.lot_size_filter.step(), Some(0.001));
+    }
+
+    #[test]
+    fn test_decode_spot_instruments_info_market() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "category": "spot",
+                "list": [{
+                    "symbol": "BTCUSDT",
+                    "baseCoin": "BTC",
+                    "quoteCoin": "USDT",
+                    "innovation": "0",
+                    "status": "Trading",
+                    "lotSizeFilter": {
+                        "basePrecision": "0.000001",
+                        "quotePrecision": "0.00000001",
+                        "minOrderQty": "0.000048",
+                        "maxOrderQty": "71.73956243",
+                        "minOrderAmt": "1",
+                        "maxOrderAmt": "2000000"
+                    },
+                    "priceFilter": { "tickSize": "0.01" }
+                }]
+            },
+            "retExtInfo": {},
+            "time": 1672712468011
+        }"#;
+
+        let response: ApiResponse<InstrumentsInfo> = decode_response(200, body).unwrap();
+        let instrument = &response.result.list[0];
+
+        assert_eq!(instrument.price_filter.min_price, None);
+        assert_eq!(instrument.lot_size_filter.qty_step, None);
+        assert_eq!(instrument.lot_size_filter.step(), Some(0.000001));
+        assert_eq!(response.result.next_page_cursor, "");
+    }
+}
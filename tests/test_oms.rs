@@ -1,446 +1,334 @@
+use std::ops::Bound;
 use rand::Rng;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use rust_workshop::instrument::{ InstrumentSpec, Price, Qty, Rounding };
+use rust_workshop::market::Side;
+use rust_workshop::oms::{ Oms, OmsError, Order, OrderState };
 
-type OrderMap = RefCell<HashMap<String, Order>>;
-
-enum AltReturn<'a> {
-	SomeOrder((&'a OrderMap, Order)),
-	SomeOrderId((&'a OrderMap, String))
+// Ticks of 0.1 and lots of 0.001, Qty(1000) is 1.0
+fn spec(symbol: &str) -> InstrumentSpec {
+	InstrumentSpec::new(symbol, 0.1, 0.001).unwrap()
 }
 
-#[derive(Clone)]
-enum OrderPosition {
-	BuySide(Order), // Use when you need the order returned
-	SellSide(Order), // Use when you need the order returned
-	BuySideId(String), // Use when you need the order_id returned
-	SellSideId(String) // Use when you need the order_id returned
+fn random_order(id: &str, side: Side, state: OrderState) -> Order {
+	let mut rng = rand::thread_rng();
+
+	Order::new(
+		&spec("BTCUSDT"),
+		String::from(id),
+		side,
+		Price(rng.gen_range(1..1_000_000)),
+		Qty(rng.gen_range(1..10_000)),
+		rng.gen::<u64>(),
+		rng.gen::<u64>()
+	)
+		.with_position_idx(rng.gen::<u8>())
+		.with_state(state)
 }
 
-#[derive(Clone)]
-enum OrderStatus {
-	Pending(OrderPosition),
-	Active(OrderPosition)
-}
+/*
+TESTS ARE HERE
+*/
 
-#[derive(Debug, PartialEq, Clone)]
-struct Order {
-	id: String,
-	price: f64,
-	qty: f64,
-	position_idx: u8,
-	created_time: i32,
-	updated_time: i32,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[derive(Debug, PartialEq)]
-struct Oms {
-	sell_side_orders_active: OrderMap,
-	sell_side_orders_pending: OrderMap,
-	buy_side_orders_active: OrderMap,
-	buy_side_orders_pending: OrderMap
-}
+    #[test]
+    fn test_new_oms() {
+    	let oms = Oms::new();
 
-impl Oms {
+    	assert_eq!(Oms::default(), oms);
+    	assert!(oms.sell_side_orders_active().borrow().is_empty());
+    	assert!(oms.sell_side_orders_pending().borrow().is_empty());
+    	assert!(oms.buy_side_orders_active().borrow().is_empty());
+    	assert!(oms.buy_side_orders_pending().borrow().is_empty());
+    }
 
-	pub fn new() -> Oms {
-		Oms { 
-			sell_side_orders_active: RefCell::new(HashMap::new()),
-			sell_side_orders_pending: RefCell::new(HashMap::new()),
-			buy_side_orders_active: RefCell::new(HashMap::new()),
-			buy_side_orders_pending: RefCell::new(HashMap::new())
-		}
-	}
-	
-	pub fn handle_mapping(&mut self, order_value: OrderStatus) -> AltReturn {
+    #[test]
+    fn test_add_oms() {
+    	let mut oms = Oms::new();
 
-		match order_value {
+    	oms.add(Side::Buy, OrderState::Active, random_order("1234", Side::Buy, OrderState::Active)).unwrap();
+    	oms.add(Side::Sell, OrderState::PartiallyFilled, random_order("123465", Side::Sell, OrderState::PartiallyFilled)).unwrap();
+    	oms.add(Side::Sell, OrderState::PendingNew, random_order("1234657", Side::Sell, OrderState::PendingNew)).unwrap();
 
-			OrderStatus::Active(order_position) => {
+    	assert_eq!(oms.buy_side_orders_active().borrow().len(), 1);
+    	assert_eq!(oms.sell_side_orders_active().borrow().len(), 1);
+    	assert_eq!(oms.sell_side_orders_pending().borrow().len(), 1);
 
-				match order_position {
-					OrderPosition::BuySide(order) => {
-						AltReturn::SomeOrder((&self.buy_side_orders_active, order))
-					}
+    	// Ids are unique across both sides
+    	let duplicate = random_order("1234", Side::Sell, OrderState::Active);
+    	assert_eq!(oms.add(Side::Sell, OrderState::Active, duplicate), Err(OmsError::DuplicateOrder("1234".to_string())));
 
-					OrderPosition::SellSide(order) => {
-						AltReturn::SomeOrder((&self.sell_side_orders_active, order))
-					}
+    	let mismatch = random_order("12345", Side::Buy, OrderState::Active);
+    	assert_eq!(oms.add(Side::Sell, OrderState::Active, mismatch), Err(OmsError::InvalidVariant { id: "12345".to_string(), side: Side::Sell, state: OrderState::Active }));
 
-					OrderPosition::BuySideId(order_id) => {
-						AltReturn::SomeOrderId((&self.buy_side_orders_active, order_id))
-					}
+    	let filled = random_order("12346578", Side::Buy, OrderState::Filled);
+    	assert_eq!(oms.add(Side::Buy, OrderState::Filled, filled), Err(OmsError::Closed { id: "12346578".to_string(), state: OrderState::Filled }));
 
-					OrderPosition::SellSideId(order_id) => {
-						AltReturn::SomeOrderId((&self.sell_side_orders_active, order_id))
-					}
-				}
-			}
+    	assert_eq!(oms.buy_side_orders_active().borrow().len(), 1);
+    	assert_eq!(oms.sell_side_orders_active().borrow().len(), 1);
+    }
 
-			OrderStatus::Pending(order_position) => {
+    #[test]
+    fn test_remove_oms() {
+    	let mut oms = Oms::new();
 
-				match order_position {
-					OrderPosition::BuySide(order) => {
-						AltReturn::SomeOrder((&self.buy_side_orders_pending, order))
-					}
+    	oms.add(Side::Buy, OrderState::Active, random_order("1234", Side::Buy, OrderState::Active)).unwrap();
+    	oms.add(Side::Sell, OrderState::Active, random_order("12345", Side::Sell, OrderState::Active)).unwrap();
 
-					OrderPosition::SellSide(order) => {
-						AltReturn::SomeOrder((&self.sell_side_orders_pending, order))
-					}
+    	assert_eq!(oms.buy_side_orders_active().borrow().len(), 1);
+    	assert_eq!(oms.sell_side_orders_active().borrow().len(), 1);
 
-					OrderPosition::BuySideId(order_id) => {
-						AltReturn::SomeOrderId((&self.buy_side_orders_pending, order_id))
-					}
+    	assert_eq!(oms.remove("1234").unwrap().side, Side::Buy);
+    	assert_eq!(oms.remove("12345").unwrap().side, Side::Sell);
+    	assert_eq!(oms.remove("12345"), Err(OmsError::UnknownOrder("12345".to_string())));
 
-					OrderPosition::SellSideId(order_id) => {
-						AltReturn::SomeOrderId((&self.sell_side_orders_pending, order_id))
-					}
-				}
-			}
-		}
-	}
-	
-	pub fn add_order (&mut self, order_value: OrderStatus) {
+    	assert_eq!(oms.buy_side_orders_active().borrow().len(), 0);
+    	assert_eq!(oms.sell_side_orders_active().borrow().len(), 0);
+    }
 
-		let AltReturn::SomeOrder((map, order)) = self.handle_mapping(order_value) else { panic!() };
+    #[test]
+    fn test_get_oms() {
+    	let mut oms = Oms::new();
 
-		map
-			.borrow_mut()
-			.insert(order.id.clone(), order);
-	}
+    	let buy_order_active = random_order("1234", Side::Buy, OrderState::Active);
+    	let sell_order_pending = random_order("12345", Side::Sell, OrderState::PendingNew);
+    	let sell_order_active = random_order("123456", Side::Sell, OrderState::Active);
 
-	pub fn delete_order(&mut self, order_value: OrderStatus) {
+    	for order in [&buy_order_active, &sell_order_pending, &sell_order_active] {
+    		oms.add(order.side, order.state, order.clone()).unwrap();
+    	}
 
-		let AltReturn::SomeOrderId((map, order_id)) = self.handle_mapping(order_value) else { panic!() };
+    	assert_eq!(oms.get("1234"), Ok(buy_order_active));
+    	assert_eq!(oms.get("12345"), Ok(sell_order_pending));
+    	assert_eq!(oms.get("123456"), Ok(sell_order_active));
 
-		map 
-			.borrow_mut()
-			.remove(&order_id);
-	}
+    	// A bad id is an error, not a panic
+    	assert_eq!(oms.get("missing"), Err(OmsError::UnknownOrder("missing".to_string())));
+    }
 
-	pub fn get_order(&mut self, order_value: OrderStatus) -> Order {
+    #[test]
+    fn test_get_inventory_delta_oms() {
+    	let mut oms = Oms::new();
 
-		let AltReturn::SomeOrderId((map, order_id)) = self.handle_mapping(order_value) else { panic!() };
+    	let orders = [
+    		random_order("1234", Side::Sell, OrderState::Active),
+    		random_order("12345", Side::Sell, OrderState::Active),
+    		random_order("123456", Side::Buy, OrderState::Active),
+    		random_order("1234567", Side::Buy, OrderState::Active)
+    	];
 
-		let binding_map = map.borrow();
+    	let delta = (orders[2].leaves_qty_value() + orders[3].leaves_qty_value()) - (orders[0].leaves_qty_value() + orders[1].leaves_qty_value());
 
-		let anw = binding_map
-			.get(&order_id)
-			.expect("Failed to get order");
+    	for order in orders {
+    		oms.add(order.side, order.state, order).unwrap();
+    	}
 
-		anw.clone()
+    	// Pending orders aren't working yet
+    	oms.add(Side::Buy, OrderState::PendingNew, random_order("12345678", Side::Buy, OrderState::PendingNew)).unwrap();
 
-	}
-	// Will return current inventory delta
-	pub fn get_inventory_delta(&self) -> f64 {
+    	let result = oms.get_inventory_delta();
 
-		let bid_delta: f64 = self.buy_side_orders_active
-			.borrow()
-			.values()
-			.map(|order| order.qty)
-			.sum();
+    	assert_eq!(delta, result);
+    }
 
-		println!("{:?}", bid_delta);
+    #[test]
+    fn test_get_size_to_target() {
+    	let mut oms = Oms::new();
 
-		let ask_delta: f64 = self.sell_side_orders_active
-			.borrow()
-			.values()
-			.map(|order| order.qty)
-			.sum();
+    	let b_1 = random_order("1234", Side::Buy, OrderState::Active);
+    	let a_1 = random_order("12345", Side::Sell, OrderState::Active);
 
-		println!("{:?}", ask_delta);
+    	let target_delta = 0.0;
+    	let anw = ((b_1.leaves_qty_value() - a_1.leaves_qty_value()) - target_delta).abs();
 
-		let value = bid_delta - ask_delta;
+    	oms.add(Side::Buy, OrderState::Active, b_1).unwrap();
+    	oms.add(Side::Sell, OrderState::Active, a_1).unwrap();
 
-		value
-	}
+    	let result = oms.get_size_to_target(target_delta);
 
-	pub fn get_size_to_target(&self, target_delta: f64) -> f64 {
-		
-		let current_delta = self
-			.get_inventory_delta();
+    	assert_eq!(anw, result);
+    }
 
-		let anw = (current_delta - target_delta).abs();
+    #[test]
+    fn test_fixed_point_order_oms() {
+    	let spec = spec("BTCUSDT");
+    	let oms = Oms::new().with_instrument(spec.clone());
+
+    	assert_eq!(oms.instrument("BTCUSDT"), Some(&spec));
+    	assert_eq!(oms.instrument("ETHUSDT"), None);
+
+    	let price = spec.price(30000.1 + 0.2, Rounding::Nearest).unwrap();
+    	let qty = spec.qty(0.1 + 0.2, Rounding::Nearest).unwrap();
+    	let order = Order::new(&spec, String::from("1234"), Side::Buy, price, qty, 0, 0);
+
+    	assert_eq!(order.price, Price(300003));
+    	assert_eq!(order.qty, Qty(300));
+    	assert_eq!(order.symbol(), "BTCUSDT");
+    	assert_eq!(order.price_value(), 30000.3);
+    	assert_eq!(order.qty_value(), 0.3);
+    }
 
-		anw
-	}
-}
+    #[test]
+    fn test_state_transitions_oms() {
+    	let mut order = Order::new(&spec("BTCUSDT"), String::from("1234"), Side::Buy, Price(1000), Qty(1000), 0, 0);
+    	assert_eq!(order.state, OrderState::New);
+
+    	order.transition(OrderState::PendingNew).unwrap();
+    	order.transition(OrderState::Active).unwrap();
+    	order.transition(OrderState::PendingCancel).unwrap();
+
+    	// Cancel rejected, back to working
+    	order.transition(OrderState::Active).unwrap();
+    	order.transition(OrderState::Cancelled).unwrap();
+
+    	// Closed orders stay closed and are left untouched
+    	let error = order.transition(OrderState::Active).unwrap_err();
+    	assert_eq!(error, OmsError::InvalidTransition { id: "1234".to_string(), from: OrderState::Cancelled, to: OrderState::Active });
+    	assert_eq!(order.state, OrderState::Cancelled);
+
+    	assert!(!OrderState::New.can_transition_to(OrderState::Filled));
+    	assert!(!OrderState::PartiallyFilled.can_transition_to(OrderState::Active));
+    	assert!(OrderState::PartiallyFilled.can_transition_to(OrderState::PartiallyFilled));
+    	assert!(!OrderState::Filled.can_transition_to(OrderState::Filled));
+    }
 
-/* 
-TESTS ARE HERE
-*/
+    #[test]
+    fn test_fills_oms() {
+    	let mut order = Order::new(&spec("BTCUSDT"), String::from("1234"), Side::Sell, Price(1000), Qty(1000), 0, 0);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    	// Can't fill an order that hasn't been sent
+    	assert!(matches!(order.fill(Qty(500), 100.0), Err(OmsError::InvalidTransition { .. })));
 
-    #[test]
-    fn test_new_oms() {
-    	let oms = Oms::new();
+    	order.transition(OrderState::PendingNew).unwrap();
+    	order.transition(OrderState::Active).unwrap();
 
-    	let oms_dummy = Oms {
-    		sell_side_orders_active: RefCell::new(HashMap::new()),
-			sell_side_orders_pending: RefCell::new(HashMap::new()),
-			buy_side_orders_active: RefCell::new(HashMap::new()),
-			buy_side_orders_pending: RefCell::new(HashMap::new())
-    	};
+    	order.fill(Qty(250), 100.0).unwrap();
+    	order.fill(Qty(250), 102.0).unwrap();
 
-    	assert_eq!(oms_dummy, oms)
-    }
+    	assert_eq!(order.state, OrderState::PartiallyFilled);
+    	assert_eq!(order.cum_filled_qty, Qty(500));
+    	assert_eq!(order.leaves_qty_value(), 0.5);
+    	assert_eq!(order.avg_fill_price, Some(101.0));
 
-    #[test]
-    fn test_add_order_oms() {
-    	let mut oms = Oms::new();
-    	let mut rng = rand::thread_rng();
-
-    	let buy_order_active = OrderStatus::Active (
-    		OrderPosition::BuySide (
-    			Order {
-    				id: String::from("1234"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let sell_order_active = OrderStatus::Active (
-    		OrderPosition::SellSide (
-    			Order {
-    				id: String::from("123465"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	oms.add_order(buy_order_active);
-    	oms.add_order(sell_order_active);
-
-    	assert_eq!(oms.buy_side_orders_active.borrow().len(), 1);
-    	assert_eq!(oms.sell_side_orders_active.borrow().len(), 1);
+    	let overfill = order.fill(Qty(750), 101.0).unwrap_err();
+    	assert_eq!(overfill, OmsError::InvalidFill { id: "1234".to_string(), qty: Qty(750), leaves_qty: Qty(500) });
+    	assert_eq!(order.cum_filled_qty, Qty(500));
+    	assert!(matches!(order.fill(Qty(0), 101.0), Err(OmsError::InvalidFill { .. })));
+
+    	// Fills racing a cancel keep it pending until the order is done
+    	order.transition(OrderState::PendingCancel).unwrap();
+    	order.fill(Qty(250), 101.0).unwrap();
+    	assert_eq!(order.state, OrderState::PendingCancel);
+
+    	order.fill(Qty(250), 101.0).unwrap();
+    	assert_eq!(order.state, OrderState::Filled);
+    	assert_eq!(order.leaves_qty, Qty(0));
     }
 
     #[test]
-    fn test_delete_order_oms() {
+    fn test_oms_lifecycle_oms() {
     	let mut oms = Oms::new();
-    	let mut rng = rand::thread_rng();
-
-    	let buy_order_active = OrderStatus::Active (
-    		OrderPosition::BuySide (
-    			Order {
-    				id: String::from("1234"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let sell_order_active = OrderStatus::Active (
-    		OrderPosition::SellSide (
-    			Order {
-    				id: String::from("12345"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	oms.add_order(buy_order_active.clone());
-    	oms.add_order(sell_order_active.clone());
-
-    	assert_eq!(oms.buy_side_orders_active.borrow().len(), 1);
-    	assert_eq!(oms.sell_side_orders_active.borrow().len(), 1);
-
-    	oms.delete_order(OrderStatus::Active(OrderPosition::BuySideId("1234".to_string())));
-    	oms.delete_order(OrderStatus::Active(OrderPosition::SellSideId("12345".to_string())));
-
-    	assert_eq!(oms.buy_side_orders_active.borrow().len(), 0);
-    	assert_eq!(oms.sell_side_orders_active.borrow().len(), 0);
 
+    	oms.add(Side::Buy, OrderState::New, Order::new(&spec("BTCUSDT"), String::from("1234"), Side::Buy, Price(1000), Qty(1000), 0, 0)).unwrap();
+    	assert_eq!(oms.buy_side_orders_pending().borrow().len(), 1);
+
+    	oms.transition("1234", OrderState::PendingNew).unwrap();
+    	oms.transition("1234", OrderState::Active).unwrap();
+    	assert!(oms.buy_side_orders_pending().borrow().is_empty());
+    	assert_eq!(oms.buy_side_orders_active().borrow().len(), 1);
+
+    	// A rejected transition leaves the order where it was
+    	assert!(oms.transition("1234", OrderState::New).is_err());
+    	assert_eq!(oms.get("1234").unwrap().state, OrderState::Active);
+
+    	let order = oms.apply_fill("1234", Qty(400), 100.0).unwrap();
+    	assert_eq!(order.state, OrderState::PartiallyFilled);
+    	assert_eq!(oms.get_inventory_delta(), 0.6);
+
+    	let order = oms.apply_fill("1234", Qty(600), 100.0).unwrap();
+    	assert_eq!(order.state, OrderState::Filled);
+    	assert!(oms.buy_side_orders_active().borrow().is_empty());
+
+    	assert_eq!(oms.apply_fill("1234", Qty(100), 100.0), Err(OmsError::UnknownOrder("1234".to_string())));
     }
 
     #[test]
-    fn test_get_order_oms() {
+    fn test_indexes_oms() {
     	let mut oms = Oms::new();
-    	let mut rng = rand::thread_rng();
-
-    	let buy_order_active = 
-			Order {
-				id: String::from("1234"),
-				price: rng.gen::<f64>(),
-				qty: rng.gen::<f64>(),
-				position_idx: rng.gen::<u8>(),
-				created_time: rng.gen::<i32>(),
-				updated_time: rng.gen::<i32>()
-			};
-
-    	let sell_order_pending = 
-			Order {
-				id: String::from("12345"),
-				price: rng.gen::<f64>(),
-				qty: rng.gen::<f64>(),
-				position_idx: rng.gen::<u8>(),
-				created_time: rng.gen::<i32>(),
-				updated_time: rng.gen::<i32>()
-			};
-
-		let sell_order_active = 
-			Order {
-				id: String::from("123456"),
-				price: rng.gen::<f64>(),
-				qty: rng.gen::<f64>(),
-				position_idx: rng.gen::<u8>(),
-				created_time: rng.gen::<i32>(),
-				updated_time: rng.gen::<i32>()
-			};
-
-		// I dont like all these clones
-		// Will be fixing
-    	oms.add_order(OrderStatus::Active(OrderPosition::BuySide(buy_order_active.clone())));
-    	oms.add_order(OrderStatus::Pending(OrderPosition::SellSide(sell_order_pending.clone())));
-    	oms.add_order(OrderStatus::Active(OrderPosition::SellSide(sell_order_active.clone())));
-
-    	let test1 = oms.get_order(OrderStatus::Active(OrderPosition::BuySideId(buy_order_active.id.clone())));
-    	let test2 = oms.get_order(OrderStatus::Pending(OrderPosition::SellSideId(sell_order_pending.id.clone())));
-    	let test3 = oms.get_order(OrderStatus::Active(OrderPosition::SellSideId(sell_order_active.id.clone())));
-
-
-    	assert_eq!(test1.clone() , buy_order_active);
-    	assert_eq!(test2.clone(), sell_order_pending);
-    	assert_eq!(test3.clone(), sell_order_active);
-    }
 
-    #[test]
-    fn test_get_inventory_delta_oms() {
+    	let orders = [
+    		("b-1", "BTCUSDT", Side::Buy, 1000, OrderState::Active),
+    		("b-2", "BTCUSDT", Side::Buy, 1010, OrderState::PendingNew),
+    		("b-3", "ETHUSDT", Side::Buy, 1020, OrderState::PartiallyFilled),
+    		("b-4", "BTCUSDT", Side::Buy, 1020, OrderState::Active),
+    		("b-5", "BTCUSDT", Side::Buy, 1030, OrderState::PartiallyFilled),
+    		("s-1", "BTCUSDT", Side::Sell, 1030, OrderState::Active)
+    	];
 
-    	let mut oms = Oms::new();
-    	let mut rng = rand::thread_rng();
-
-    	let sell_order_active = OrderStatus::Active (
-    		OrderPosition::SellSide (
-    			Order {
-    				id: String::from("1234"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let sell_order_active_2 = OrderStatus::Active (
-    		OrderPosition::SellSide (
-    			Order {
-    				id: String::from("12345"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let buy_order_active = OrderStatus::Active (
-    		OrderPosition::BuySide (
-    			Order {
-    				id: String::from("123456"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let buy_order_active_2 = OrderStatus::Active (
-    		OrderPosition::BuySide (
-    			Order {
-    				id: String::from("1234567"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let OrderStatus::Active(OrderPosition::BuySide(b_1)) = &buy_order_active else { todo!() }; 
-    	let OrderStatus::Active(OrderPosition::BuySide(b_2)) = &buy_order_active_2 else { todo!() };
-
-    	let OrderStatus::Active(OrderPosition::SellSide(a_1)) = &sell_order_active else { todo!() };
-    	let OrderStatus::Active(OrderPosition::SellSide(a_2)) = &sell_order_active_2 else { todo!() };
-
-    	let delta = ((b_1.qty + b_2.qty) - (a_1.qty + a_2.qty)) as f64;
-
-    	oms.add_order(sell_order_active);
-    	oms.add_order(sell_order_active_2);
-    	oms.add_order(buy_order_active);
-    	oms.add_order(buy_order_active_2);
+    	for (id, symbol, side, price, state) in orders {
+    		let order = Order::new(&spec(symbol), id.to_string(), side, Price(price), Qty(1000), 0, 0)
+    			.with_order_link_id(&format!("link-{}", id))
+    			.with_state(state);
 
-    	let result = oms.get_inventory_delta();
+    		oms.add(side, state, order).unwrap();
+    	}
 
-    	assert_eq!(delta, result);
+    	let ids = |orders: Vec<Order>| orders.into_iter().map(|order| order.id).collect::<Vec<_>>();
+
+    	assert_eq!(oms.get_by_link_id("link-b-3").unwrap().id, "b-3");
+    	assert_eq!(oms.get_by_link_id("link-x"), Err(OmsError::UnknownOrder("link-x".to_string())));
+
+    	assert_eq!(ids(oms.orders_by_symbol("BTCUSDT")), vec!["b-1", "b-2", "b-4", "b-5", "s-1"]);
+    	assert_eq!(ids(oms.orders_by_state(OrderState::Active)), vec!["b-1", "b-4", "s-1"]);
+
+    	// Prices are per symbol, the ETHUSDT bid at the same ticks isn't included
+    	assert_eq!(ids(oms.orders_by_price("BTCUSDT", Side::Buy, Price(1005)..=Price(1020))), vec!["b-2", "b-4"]);
+    	assert_eq!(ids(oms.orders_by_price("ETHUSDT", Side::Buy, ..)), vec!["b-3"]);
+
+    	// Active bids above 100, the pending order isn't working yet
+    	assert_eq!(ids(oms.working_above("BTCUSDT", Side::Buy, Price(1000))), vec!["b-4", "b-5"]);
+    	assert_eq!(ids(oms.working_below("BTCUSDT", Side::Buy, Price(1020))), vec!["b-1"]);
+
+    	// Moving between states keeps every index in step
+    	oms.transition("b-2", OrderState::Active).unwrap();
+    	assert_eq!(ids(oms.orders_by_state(OrderState::Active)), vec!["b-1", "b-2", "b-4", "s-1"]);
+    	assert!(oms.orders_by_state(OrderState::PendingNew).is_empty());
+
+    	oms.transition("b-4", OrderState::Cancelled).unwrap();
+    	assert_eq!(ids(oms.working_above("BTCUSDT", Side::Buy, Price(1000))), vec!["b-2", "b-5"]);
+    	assert_eq!(oms.get_by_link_id("link-b-4"), Err(OmsError::UnknownOrder("link-b-4".to_string())));
+
+    	oms.remove("b-3").unwrap();
+    	assert!(oms.orders_by_symbol("ETHUSDT").is_empty());
+    	assert_eq!(oms.get("b-3"), Err(OmsError::UnknownOrder("b-3".to_string())));
+
+    	// Link ids are unique too
+    	let duplicate = Order::new(&spec("BTCUSDT"), "b-6".to_string(), Side::Buy, Price(990), Qty(1000), 0, 0).with_order_link_id("link-b-1");
+    	assert_eq!(oms.add(Side::Buy, OrderState::New, duplicate), Err(OmsError::DuplicateOrder("b-6".to_string())));
     }
 
     #[test]
-    fn test_get_size_to_target() {
-
+    fn test_empty_price_ranges_oms() {
     	let mut oms = Oms::new();
-    	let mut rng = rand::thread_rng();
-
-    	let buy_order_active = OrderStatus::Active (
-    		OrderPosition::BuySide (
-    			Order {
-    				id: String::from("1234"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let sell_order_active = OrderStatus::Active (
-    		OrderPosition::SellSide (
-    			Order {
-    				id: String::from("12345"),
-    				price: rng.gen::<f64>(),
-    				qty: rng.gen::<f64>(),
-    				position_idx: rng.gen::<u8>(),
-    				created_time: rng.gen::<i32>(),
-    				updated_time: rng.gen::<i32>()
-    			}
-    		)
-    	);
-
-    	let OrderStatus::Active(OrderPosition::BuySide(b_1)) = &buy_order_active else { todo!() }; 
-    	let OrderStatus::Active(OrderPosition::SellSide(a_1)) = &sell_order_active else { todo!() };
 
-    	let target_delta = 0.0;
-    	let anw = ((b_1.qty - a_1.qty) - target_delta as f64).abs();
+    	for (id, price) in [("b-1", 1000), ("b-2", 1010)] {
+    		let order = Order::new(&spec("BTCUSDT"), id.to_string(), Side::Buy, Price(price), Qty(1000), 0, 0).with_state(OrderState::Active);
+    		oms.add(Side::Buy, OrderState::Active, order).unwrap();
+    	}
 
-    	oms.add_order(buy_order_active);
-    	oms.add_order(sell_order_active);
+    	let excluded = (Bound::Excluded(Price(1000)), Bound::Excluded(Price(1000)));
+    	let reversed = (Bound::Included(Price(1010)), Bound::Excluded(Price(1000)));
 
-    	let result = oms.get_size_to_target(target_delta);
+    	// Empty and reversed ranges match nothing rather than panicking
+    	assert!(oms.orders_by_price("BTCUSDT", Side::Buy, excluded).is_empty());
+    	assert!(oms.orders_by_price("BTCUSDT", Side::Buy, reversed).is_empty());
+    	assert!(oms.orders_by_price("BTCUSDT", Side::Buy, Price(1010)..=Price(1000)).is_empty());
+    	assert!(oms.orders_by_price("BTCUSDT", Side::Buy, Price(1000)..Price(1000)).is_empty());
 
-    	assert_eq!(anw, result);
+    	assert_eq!(oms.orders_by_price("BTCUSDT", Side::Buy, Price(1000)..=Price(1000)).len(), 1);
+    	assert_eq!(oms.working_above("BTCUSDT", Side::Buy, Price(1010)), vec![]);
+    	assert_eq!(oms.working_below("BTCUSDT", Side::Buy, Price(1000)), vec![]);
     }
-}
\ No newline at end of file
+}
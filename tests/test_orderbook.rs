@@ -1,243 +1,25 @@
 use rand::Rng;
-use std::cmp::Ordering;
-use std::cell::RefCell;
-use std::collections::BTreeMap;
-use ordered_float::OrderedFloat;
-
-type BidsMap = RefCell<BTreeMap<OrderedFloat<f64>, RestingOrder>>;
-type AsksMap = RefCell<BTreeMap<OrderedFloat<f64>, RestingOrder>>;
-
-#[derive(Clone)]
-enum RestingOrderType {
-    BidOrder(RestingOrder),
-    AskOrder(RestingOrder),
-    BidPrice(f64),
-    AskPrice(f64)
+use rust_workshop::instrument::{ InstrumentSpec, Rounding };
+use rust_workshop::market::{ Level, Side };
+use rust_workshop::orderbook::{ BookSide, Depth, DepthPoint, Distance, FillCost, Orderbook, OrderbookError, RestingOrder, RestingOrderType, MAX_SKEW };
+
+// bids 99 x 1, 98 x 3 / asks 101 x 3, 102 x 1, 110 x 4, mid 100
+fn signal_book() -> Orderbook {
+    let mut orderbook = Orderbook::new();
+    let bids = vec![Level { price: 99.0, size: 1.0 }, Level { price: 98.0, size: 3.0 }];
+    let asks = vec![Level { price: 101.0, size: 3.0 }, Level { price: 102.0, size: 1.0 }, Level { price: 110.0, size: 4.0 }];
+
+    orderbook.apply_snapshot(&bids, &asks, 1, 1, 1);
+    orderbook
 }
 
-#[derive(Clone, Debug, PartialEq)]
-struct RestingOrder {
-    price: f64,
-    size: f64,
-    ts: u128
+// Books without a spec snap prices and sizes to eight decimals
+fn on_grid(value: f64) -> f64 {
+    InstrumentSpec::fallback("").round_price(value, Rounding::Nearest).unwrap()
 }
 
-#[derive(Clone, Debug, PartialEq)]
-struct Orderbook {
-    asks: AsksMap,
-    bids: BidsMap,
-    last_update_time: u128,
-}
-
-impl Orderbook {
-    fn new() -> Orderbook {
-        Orderbook {
-            asks: RefCell::new(BTreeMap::new()),
-            bids: RefCell::new(BTreeMap::new()),
-            last_update_time: 0
-        }
-    }
-
-    // Inserts resting order into orderbook 
-    fn insert_order (&mut self, order: RestingOrderType) {
-
-        match order {
-            RestingOrderType::BidOrder(bid) => {
-                let price = OrderedFloat(bid.price);
-                self.last_update_time = bid.ts; 
-
-                self.bids
-                    .borrow_mut()
-                    .insert(price, bid);
-            }
-
-            RestingOrderType::AskOrder(ask) => {
-                let price = OrderedFloat(ask.price);
-                self.last_update_time = ask.ts; 
-
-                self.asks
-                    .borrow_mut()
-                    .insert(price, ask);
-            }
-
-            _ => todo!()
-        }
-    }
-    // Returns ask closest to mid-price
-    fn get_ask(&self) -> RestingOrder {
-
-        let binding = self.asks
-            .borrow();
-
-        let (_key, value) = binding
-            .first_key_value()
-            .expect("Failed to get first key value");
-
-        RestingOrder { price: value.price, size: value.size,  ts: value.ts }
-    }
-    // Returns bid closest to mid-price
-    fn get_bid(&self) -> RestingOrder {
-
-        let binding = self.bids 
-            .borrow();
-
-        let (_key, value) = binding
-            .last_key_value()
-            .expect("Failed to get last key value");
-
-        RestingOrder { price: value.price, size: value.size, ts: value.ts }
-    }
-    // Returns all asks
-    fn get_asks(&self) -> &BidsMap {
-        let asks = &self.asks;
-
-        asks
-    }
-    // Returns all bids
-    fn get_bids(&self) -> &AsksMap {
-        let bids = &self.bids;
-
-        bids
-    }
-    // Calculates the current orderbook skew
-    fn get_ordebook_skew(&self) -> f64 {
-
-        let buy_side_depth: f64 = self
-            .bids
-            .borrow()
-            .values()
-            .map(|order| order.size)
-            .sum();
-
-        let sell_side_depth: f64 = self
-            .asks
-            .borrow()
-            .values()
-            .map(|order| order.size)
-            .sum();
-
-        let value = buy_side_depth.ln() - sell_side_depth.ln();
-
-        value
-    }
-    // Calculates the current orderbook skew by range
-    fn get_ordebook_skew_by_range(&self, diff: &f64) -> f64 {
-        let mid_price = self.get_mid_price();
-
-        let lower_bound = OrderedFloat(mid_price - diff);
-        let upper_bound = OrderedFloat(mid_price + diff);
-
-        let a_binding = self
-            .asks
-            .borrow();
-
-        let b_binding = self
-            .bids
-            .borrow();
-
-        let a_value = a_binding
-            .range(..upper_bound);
-
-        let b_value = b_binding
-            .range(lower_bound..);
-
-        let ask_side_depth_range: f64 = a_value
-            .map(|order| order.1.size)
-            .sum();
-
-        let buy_side_depth_range: f64 = b_value
-            .map(|order| order.1.size)
-            .sum();
-
-        let value = buy_side_depth_range.ln() - ask_side_depth_range.ln();
-
-        value
-        
-    }
-    // Gets orderbook mid price
-    fn get_mid_price(&self) -> f64 {
-        let bid_price = self
-            .get_bid()
-            .price;
-
-        let ask_price = self
-            .get_ask()
-            .price;
-
-        let mid_price = (bid_price + ask_price) / 2.0;
-
-        mid_price
-    }
-    // Gets orderbook spread
-    fn get_orderbook_spread(&self) -> f64 {
-        let bid_price = self
-            .get_bid()
-            .price;
-
-        let ask_price = self
-            .get_ask()
-            .price;
-
-        let spread = ask_price - bid_price;
-
-        spread
-    }
-    // Checks to see if your trade size can be filled in full at a specific price
-    // ToDo: Determine what should happen at equal
-    // true = Trade is safe 
-    // false = Trade is unsafe
-    fn safety_check_size (&self, price: RestingOrderType, size: f64) -> bool {
-
-        match price {
-            RestingOrderType::BidPrice(bid) => {
-                let check_price = OrderedFloat(bid);
-                let check_size = self.bids
-                    .borrow()
-                    .get(&check_price)
-                    .expect("Failed to get bid size")
-                    .size;
-
-                let result = size.partial_cmp(&check_size)
-                    .expect("Failed to compare values");
-
-                match result {
-                    Ordering::Greater => false,
-                    Ordering::Equal => true,
-                    Ordering::Less => true
-                }
-            }
-
-            RestingOrderType::AskPrice(ask) => {
-                let check_price = OrderedFloat(ask);
-                let check_size = self.asks 
-                    .borrow()
-                    .get(&check_price)
-                    .expect("Failed to get ask size")
-                    .size;
-
-                let result = size.partial_cmp(&check_size)
-                    .expect("Failed to compare values");
-
-                match result {
-                    Ordering::Greater => false,
-                    Ordering::Equal => true,
-                    Ordering::Less => true
-                }
-            }
-
-            _ => todo!()
-        }
-    }
-    // Safety Check
-    fn safety_check_spread(&self, max_spread: f64) -> bool {
-        let current_spread = self.get_orderbook_spread();
-
-        if current_spread > max_spread {
-            false
-        } else {
-            true
-        }
-    }
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
 }
 
 /*
@@ -252,13 +34,10 @@ mod tests {
     fn test_new_orderbook() {
         let result = Orderbook::new();
 
-        let dummy = Orderbook {
-            asks: RefCell::new(BTreeMap::new()),
-            bids: RefCell::new(BTreeMap::new()),
-            last_update_time: 0
-        };
-
-        assert_eq!(result, dummy)
+        assert_eq!(result, Orderbook::default());
+        assert!(result.get_asks().borrow().is_empty());
+        assert!(result.get_bids().borrow().is_empty());
+        assert_eq!(result.last_update_time(), 0)
     }
 
     #[test]
@@ -278,12 +57,12 @@ mod tests {
             ts: 1_000_100
         };
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask)).unwrap();
 
-        assert_eq!(orderbook.asks.borrow().len(), 1);
-        assert_eq!(orderbook.bids.borrow().len(), 1);
-        assert_eq!(orderbook.last_update_time, 1_000_100)
+        assert_eq!(orderbook.get_asks().borrow().len(), 1);
+        assert_eq!(orderbook.get_bids().borrow().len(), 1);
+        assert_eq!(orderbook.last_update_time(), 1_000_100)
     }
     
     #[test]
@@ -293,30 +72,30 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_ask_1 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_ask_2 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let test_copy_1 = resting_order_ask_1.clone();
         let test_copy_2 = resting_order_ask_2.clone();
 
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2));
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2)).unwrap();
 
         let ask = orderbook.get_ask();
 
         if test_copy_1.price > test_copy_2.price {
-            assert_eq!(ask, test_copy_2);
+            assert_eq!(ask, Some(test_copy_2));
 
         } else {
-            assert_eq!(ask, test_copy_1)
+            assert_eq!(ask, Some(test_copy_1))
         }
     }
 
@@ -327,30 +106,30 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_bid_1 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_bid_2 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let test_copy_1 = resting_order_bid_1.clone();
         let test_copy_2 = resting_order_bid_2.clone();
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1)).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2)).unwrap();
 
         let bid = orderbook.get_bid();
 
         if test_copy_1.price > test_copy_2.price {
-            assert_eq!(bid, test_copy_1);
+            assert_eq!(bid, Some(test_copy_1));
 
         } else {
-            assert_eq!(bid, test_copy_2)
+            assert_eq!(bid, Some(test_copy_2))
         }
 
     }
@@ -362,24 +141,24 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_bid_1 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_bid_2 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2.clone())).unwrap();
 
-        let bids = orderbook.get_bids();
-        let should_be_bids = &orderbook.bids;
+        let bids = orderbook.get_bids().borrow();
 
-        assert_eq!(bids, should_be_bids);
+        assert!(bids.values().any(|order| *order == resting_order_bid_1));
+        assert!(bids.values().any(|order| *order == resting_order_bid_2));
     }
 
     #[test]
@@ -389,24 +168,24 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_ask_1 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_ask_2 = RestingOrder {
-            price: rng.gen::<f64>(),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen::<f64>()),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2));
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2.clone())).unwrap();
 
-        let asks = orderbook.get_asks();
-        let should_be_asks = &orderbook.asks;
+        let asks = orderbook.get_asks().borrow();
 
-        assert_eq!(asks, should_be_asks);
+        assert!(asks.values().any(|order| *order == resting_order_ask_1));
+        assert!(asks.values().any(|order| *order == resting_order_ask_2));
     }
 
     #[test]
@@ -426,16 +205,16 @@ mod tests {
             ts: 1_000_200
         };
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask)).unwrap();
         // Bids Block
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 110.0), false);
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 95.0), true);
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 100.0), true);
+        assert!(!orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 110.0).unwrap());
+        assert!(orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 95.0).unwrap());
+        assert!(orderbook.safety_check_size(RestingOrderType::BidPrice(10.0), 100.0).unwrap());
         // Asks Block
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 11.0), false);
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 9.0), true);
-        assert_eq!(orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 10.0), true);
+        assert!(!orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 11.0).unwrap());
+        assert!(orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 9.0).unwrap());
+        assert!(orderbook.safety_check_size(RestingOrderType::AskPrice(11.0), 10.0).unwrap());
     }
 
     #[test]
@@ -445,25 +224,25 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_ask = RestingOrder {
-            price: rng.gen_range(50.0..100.0),
+            price: on_grid(rng.gen_range(50.0..100.0)),
             size: 10.0,
             ts: 1_000_000
         };
 
         let resting_order_ask_2 = RestingOrder {
-            price: rng.gen_range(50.0..100.0),
+            price: on_grid(rng.gen_range(50.0..100.0)),
             size: 10.0,
             ts: 1_000_000
         };
 
         let resting_order_bid = RestingOrder {
-            price: rng.gen_range(0.1..49.9),
+            price: on_grid(rng.gen_range(0.1..49.9)),
             size: 100.0,
             ts: 1_000_200
         };
 
         let resting_order_bid_2 = RestingOrder {
-            price: rng.gen_range(0.1..49.9),
+            price: on_grid(rng.gen_range(0.1..49.9)),
             size: 100.0,
             ts: 1_000_200
         };
@@ -478,12 +257,12 @@ mod tests {
 
         let result = (ask + bid) / 2.0;
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask)).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2)).unwrap();
 
-        let mid_price = orderbook.get_mid_price();
+        let mid_price = orderbook.get_mid_price().unwrap();
 
         assert_eq!(mid_price, result);
     }
@@ -495,26 +274,26 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_ask_2 = RestingOrder {
-            price: rng.gen_range(50.0..100.0),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(50.0..100.0)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_ask = RestingOrder {
-            price: rng.gen_range(50.0..100.0),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(50.0..100.0)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_bid = RestingOrder {
-            price: rng.gen_range(0.1..49.9),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(0.1..49.9)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_bid_2 = RestingOrder {
-            price: rng.gen_range(0.1..49.9),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(0.1..49.9)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
@@ -524,12 +303,12 @@ mod tests {
         let a2 = resting_order_ask_2.size;
 
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask)).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2)).unwrap();
 
-        let anw = ((b1 + b2) as f64).ln() - ((a1 + a2) as f64).ln();
+        let anw = (b1 + b2).ln() - (a1 + a2).ln();
 
         assert_eq!(anw, orderbook.get_ordebook_skew());
     }
@@ -541,24 +320,24 @@ mod tests {
         let mut rng = rand::thread_rng();
 
         let resting_order_ask = RestingOrder {
-            price: rng.gen_range(50.0..100.0),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(50.0..100.0)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let resting_order_bid = RestingOrder {
-            price: rng.gen_range(0.1..49.9),
-            size: rng.gen::<f64>(),
+            price: on_grid(rng.gen_range(0.1..49.9)),
+            size: on_grid(rng.gen::<f64>()),
             ts: rng.gen::<u128>()
         };
 
         let b1 = resting_order_bid.price;
         let a1 = resting_order_ask.price;
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid)).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask)).unwrap();
 
-        let spread = orderbook.get_orderbook_spread();
+        let spread = orderbook.get_orderbook_spread().unwrap();
         let anw = a1 - b1;
 
         assert_eq!(anw, spread);
@@ -572,52 +351,52 @@ mod tests {
 
         let resting_order_bid_1 = RestingOrder {
             price: 50.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
         let resting_order_bid_2 = RestingOrder {
             price: 45.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
         let resting_order_bid_3 = RestingOrder {
             price: 40.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
         let resting_order_ask_1 = RestingOrder {
             price: 60.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
         let resting_order_ask_2 = RestingOrder {
             price: 65.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
         let resting_order_ask_3 = RestingOrder {
             price: 80.0,
-            size: rng.gen::<f64>(),
+            size: on_grid(rng.gen::<f64>()),
             ts: 1_000_200
         };
 
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1.clone()));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2.clone()));
-        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_3.clone()));
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_1.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_2.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(resting_order_bid_3.clone())).unwrap();
 
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1.clone()));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2.clone()));
-        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_3.clone()));
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_1.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_2.clone())).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(resting_order_ask_3.clone())).unwrap();
 
         let range_diff = 20.0;
-        let mid_price = orderbook.get_mid_price();
+        let mid_price = orderbook.get_mid_price().unwrap();
 
-        let sum_bid: f64 = vec![resting_order_bid_1.clone(), resting_order_bid_2.clone(), resting_order_bid_3.clone()]
+        let sum_bid: f64 = [resting_order_bid_3, resting_order_bid_2, resting_order_bid_1]
             .iter()
             .filter_map(|order| {
                 if order.price > (mid_price - range_diff) {
@@ -628,7 +407,7 @@ mod tests {
             })
             .sum();
 
-        let sum_ask: f64 = vec![resting_order_ask_1.clone(), resting_order_ask_2.clone(), resting_order_ask_3.clone()]
+        let sum_ask: f64 = [resting_order_ask_1, resting_order_ask_2, resting_order_ask_3]
             .iter()
             .filter_map(|order| {
                 if order.price < (mid_price + range_diff) {
@@ -640,8 +419,429 @@ mod tests {
             .sum();
 
         let anw = sum_bid.ln() - sum_ask.ln();
-        let result = orderbook.get_ordebook_skew_by_range(&range_diff);
+        let result = orderbook.get_ordebook_skew_by_range(&range_diff).unwrap();
 
         assert_eq!(anw, result);
     }
+
+    #[test]
+    fn test_apply_snapshot_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(1.0, 1.0, 1))).unwrap();
+
+        let bids = [Level { price: 100.0, size: 2.0 }, Level { price: 99.5, size: 3.0 }];
+        let asks = [Level { price: 100.5, size: 1.0 }];
+
+        orderbook.apply_snapshot(&bids, &asks, 10, 500, 1_000);
+
+        // The stale hand inserted bid is gone
+        assert_eq!(orderbook.get_bids().borrow().len(), 2);
+        assert_eq!(orderbook.get_bid().unwrap().price, 100.0);
+        assert_eq!(orderbook.get_ask().unwrap().price, 100.5);
+        assert_eq!(orderbook.update_id(), Some(10));
+        assert_eq!(orderbook.seq(), 500);
+        assert_eq!(orderbook.last_update_time(), 1_000);
+    }
+
+    #[test]
+    fn test_apply_delta_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        let bids = [Level { price: 100.0, size: 2.0 }, Level { price: 99.5, size: 3.0 }];
+        let asks = [Level { price: 100.5, size: 1.0 }];
+
+        assert_eq!(orderbook.apply_delta(&bids, &asks, 1, 1, 1), Err(OrderbookError::NoSnapshot));
+
+        orderbook.apply_snapshot(&bids, &asks, 10, 500, 1_000);
+
+        let delta_bids = [Level { price: 100.0, size: 0.0 }, Level { price: 99.5, size: 4.0 }];
+        let delta_asks = [Level { price: 100.25, size: 0.5 }];
+
+        orderbook.apply_delta(&delta_bids, &delta_asks, 11, 503, 1_010).unwrap();
+
+        assert_eq!(orderbook.get_bids().borrow().len(), 1);
+        assert_eq!(orderbook.get_bid().unwrap(), RestingOrder::new(99.5, 4.0, 1_010));
+        assert_eq!(orderbook.get_ask().unwrap().price, 100.25);
+        assert_eq!(orderbook.update_id(), Some(11));
+        assert_eq!(orderbook.seq(), 503);
+    }
+
+    #[test]
+    fn test_apply_delta_gap_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        let bids = [Level { price: 100.0, size: 2.0 }];
+        let asks = [Level { price: 100.5, size: 1.0 }];
+
+        orderbook.apply_snapshot(&bids, &asks, 10, 500, 1_000);
+
+        let delta_bids = [Level { price: 100.0, size: 0.0 }];
+        let result = orderbook.apply_delta(&delta_bids, &[], 12, 505, 1_010);
+
+        assert_eq!(result, Err(OrderbookError::Gap { expected: 11, received: 12 }));
+        assert_eq!(orderbook.get_bid().unwrap().price, 100.0);
+
+        // Every delta is rejected until a new snapshot arrives
+        assert_eq!(orderbook.apply_delta(&delta_bids, &[], 11, 504, 1_010), Err(OrderbookError::NoSnapshot));
+
+        orderbook.apply_snapshot(&bids, &asks, 1, 510, 1_020);
+        assert!(orderbook.apply_delta(&delta_bids, &[], 2, 511, 1_030).is_ok());
+        assert!(orderbook.get_bids().borrow().is_empty());
+    }
+
+    #[test]
+    fn test_remove_level_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(10.0, 5.0, 1))).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(9.0, 2.0, 2))).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(11.0, 3.0, 3))).unwrap();
+
+        let removed = orderbook.remove_level(RestingOrderType::BidPrice(10.0));
+
+        assert_eq!(removed, Some(RestingOrder::new(10.0, 5.0, 1)));
+        assert_eq!(orderbook.get_bid().unwrap().price, 9.0);
+        assert_eq!(orderbook.remove_level(RestingOrderType::BidPrice(10.0)), None);
+
+        // A bare price has no size, inserting it is an error and leaves the level alone
+        let error = orderbook.insert_order(RestingOrderType::AskPrice(11.0));
+
+        assert_eq!(error, Err(OrderbookError::NotAnOrder { side: BookSide::Asks, price: 11.0 }));
+        assert_eq!(orderbook.get_ask(), Some(RestingOrder::new(11.0, 3.0, 3)));
+    }
+
+    #[test]
+    fn test_reduce_size_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(11.0, 0.3, 1))).unwrap();
+
+        let previous = orderbook.reduce_size(RestingOrderType::AskPrice(11.0), 0.1).unwrap();
+
+        assert_eq!(previous, Some(RestingOrder::new(11.0, 0.3, 1)));
+
+        // Negative and NaN sizes would grow or poison the level
+        assert_eq!(orderbook.reduce_size(RestingOrderType::AskPrice(11.0), -0.1), Err(OrderbookError::InvalidSize(-0.1)));
+        assert!(matches!(orderbook.reduce_size(RestingOrderType::AskPrice(11.0), f64::NAN), Err(OrderbookError::InvalidSize(_))));
+        assert!((orderbook.get_ask().unwrap().size - 0.2).abs() < 1e-12);
+
+        // 0.3 - 0.1 - 0.1 - 0.1 leaves float residue, the level must still go
+        orderbook.reduce_size(RestingOrderType::AskPrice(11.0), 0.1).unwrap();
+        orderbook.reduce_size(RestingOrderType::AskPrice(11.0), 0.1).unwrap();
+
+        assert!(orderbook.get_asks().borrow().is_empty());
+        assert_eq!(orderbook.reduce_size(RestingOrderType::AskPrice(11.0), 0.1), Ok(None));
+    }
+
+    #[test]
+    fn test_clear_side_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(10.0, 5.0, 1))).unwrap();
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(9.0, 2.0, 2))).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(11.0, 3.0, 3))).unwrap();
+
+        let cleared = orderbook.clear_side(BookSide::Bids);
+
+        assert_eq!(cleared.len(), 2);
+        assert_eq!(cleared.values().next(), Some(&RestingOrder::new(9.0, 2.0, 2)));
+        assert!(orderbook.get_bids().borrow().is_empty());
+        assert_eq!(orderbook.get_asks().borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_queries_on_empty_book_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        assert_eq!(orderbook.get_bid(), None);
+        assert_eq!(orderbook.get_ask(), None);
+        assert_eq!(orderbook.get_mid_price(), Err(OrderbookError::EmptySide(BookSide::Bids)));
+        assert_eq!(orderbook.get_ordebook_skew(), 0.0);
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(10.0, 5.0, 1))).unwrap();
+
+        assert_eq!(orderbook.get_orderbook_spread(), Err(OrderbookError::EmptySide(BookSide::Asks)));
+        assert_eq!(orderbook.safety_check_spread(1.0), Err(OrderbookError::EmptySide(BookSide::Asks)));
+        assert_eq!(orderbook.get_ordebook_skew_by_range(&1.0), Err(OrderbookError::EmptySide(BookSide::Asks)));
+    }
+
+    #[test]
+    fn test_one_sided_skew_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(10.0, 5.0, 1))).unwrap();
+        assert_eq!(orderbook.get_ordebook_skew(), MAX_SKEW);
+
+        orderbook.clear_side(BookSide::Bids);
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(11.0, 5.0, 1))).unwrap();
+        assert_eq!(orderbook.get_ordebook_skew(), -MAX_SKEW);
+    }
+
+    #[test]
+    fn test_safety_check_size_missing_level_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(11.0, 5.0, 1))).unwrap();
+
+        let missing = orderbook.safety_check_size(RestingOrderType::AskPrice(12.0), 1.0);
+        assert_eq!(missing, Err(OrderbookError::MissingLevel { side: BookSide::Asks, price: 12.0 }));
+
+        // Full orders are checked against the level at their price
+        let order = RestingOrderType::AskOrder(RestingOrder::new(11.0, 1.0, 1));
+        assert_eq!(orderbook.safety_check_size(order, 6.0), Ok(false));
+    }
+
+    #[test]
+    fn test_cost_to_fill_orderbook() {
+        let mut orderbook = Orderbook::new();
+        let bids = vec![Level { price: 99.0, size: 1.0 }, Level { price: 98.0, size: 2.0 }];
+        let asks = vec![Level { price: 101.0, size: 1.0 }, Level { price: 102.0, size: 2.0 }, Level { price: 104.0, size: 5.0 }];
+
+        orderbook.apply_snapshot(&bids, &asks, 1, 1, 1);
+
+        // Buys walk the asks upwards: 1 @ 101 + 1 @ 102
+        let cost = orderbook.cost_to_fill(Side::Buy, 2.0).unwrap();
+        assert_eq!(cost, FillCost {
+            filled: 2.0,
+            avg_price: 101.5,
+            worst_price: 102.0,
+            notional: 203.0,
+            levels: 2,
+            slippage_bps: Some(150.0),
+            remainder: 0.0
+        });
+        assert!(cost.is_complete());
+
+        // Sells walk the bids downwards: 1 @ 99 + 2 @ 98, with 1 left unfilled
+        let cost = orderbook.cost_to_fill(Side::Sell, 4.0).unwrap();
+        assert_eq!(cost.filled, 3.0);
+        assert_eq!(cost.notional, 295.0);
+        assert_eq!(cost.worst_price, 98.0);
+        assert_eq!(cost.levels, 2);
+        assert_eq!(cost.remainder, 1.0);
+        assert!(cost.slippage_bps.unwrap() > 0.0);
+        assert!(!cost.is_complete());
+
+        // Within the top level there is no walk, only the half spread
+        let cost = orderbook.cost_to_fill(Side::Buy, 0.5).unwrap();
+        assert_eq!(cost.levels, 1);
+        assert_eq!(cost.avg_price, 101.0);
+        assert_eq!(cost.slippage_bps, Some(100.0));
+    }
+
+    #[test]
+    fn test_cost_to_fill_one_sided_orderbook() {
+        let mut orderbook = Orderbook::new();
+        let asks = vec![Level { price: 101.0, size: 1.0 }, Level { price: 103.0, size: 1.0 }];
+
+        orderbook.apply_snapshot(&[], &asks, 1, 1, 1);
+
+        // No bids means no mid, everything but slippage still comes from the asks
+        let cost = orderbook.cost_to_fill(Side::Buy, 3.0).unwrap();
+        assert_eq!(cost, FillCost {
+            filled: 2.0,
+            avg_price: 102.0,
+            worst_price: 103.0,
+            notional: 204.0,
+            levels: 2,
+            slippage_bps: None,
+            remainder: 1.0
+        });
+
+        assert_eq!(orderbook.cost_to_fill(Side::Sell, 1.0), Err(OrderbookError::EmptySide(BookSide::Bids)));
+    }
+
+    #[test]
+    fn test_cost_to_fill_errors_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        assert_eq!(orderbook.cost_to_fill(Side::Buy, 1.0), Err(OrderbookError::EmptySide(BookSide::Asks)));
+
+        orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(99.0, 1.0, 1))).unwrap();
+        orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(101.0, 1.0, 1))).unwrap();
+
+        assert_eq!(orderbook.cost_to_fill(Side::Buy, 0.0), Err(OrderbookError::InvalidSize(0.0)));
+        assert_eq!(orderbook.cost_to_fill(Side::Sell, -1.0), Err(OrderbookError::InvalidSize(-1.0)));
+    }
+
+    #[test]
+    fn test_microprice_and_weighted_mid_orderbook() {
+        let orderbook = signal_book();
+
+        // More size on the ask pulls fair value towards the bid
+        assert!(approx(orderbook.get_microprice().unwrap(), 99.5));
+        assert_eq!(orderbook.get_weighted_mid(1), orderbook.get_microprice());
+        assert!(approx(orderbook.get_weighted_mid(2).unwrap(), 99.75));
+
+        assert_eq!(orderbook.get_weighted_mid(0), Err(OrderbookError::InvalidDepth(Depth::Levels(0))));
+        assert_eq!(Orderbook::new().get_microprice(), Err(OrderbookError::EmptySide(BookSide::Bids)));
+    }
+
+    #[test]
+    fn test_imbalance_orderbook() {
+        let orderbook = signal_book();
+
+        assert!(approx(orderbook.get_imbalance(Depth::Levels(1)).unwrap(), -0.5));
+        assert!(approx(orderbook.get_imbalance(Depth::Levels(3)).unwrap(), -1.0 / 3.0));
+
+        // 150bps of 100 reaches 98.5 / 101.5, 250bps reaches 97.5 / 102.5
+        assert!(approx(orderbook.get_imbalance(Depth::Bps(150.0)).unwrap(), -0.5));
+        assert!(approx(orderbook.get_imbalance(Depth::Bps(250.0)).unwrap(), 0.0));
+
+        assert_eq!(orderbook.get_imbalance(Depth::Bps(-1.0)), Err(OrderbookError::InvalidDepth(Depth::Bps(-1.0))));
+    }
+
+    #[test]
+    fn test_decay_imbalance_orderbook() {
+        let orderbook = signal_book();
+
+        // No decay is the whole book, strong decay converges on the touch
+        assert!(approx(orderbook.get_decay_imbalance(0.0).unwrap(), orderbook.get_imbalance(Depth::Levels(usize::MAX)).unwrap()));
+        assert!((orderbook.get_decay_imbalance(0.1).unwrap() + 0.5).abs() < 1e-3);
+
+        assert_eq!(orderbook.get_decay_imbalance(-0.1), Err(OrderbookError::InvalidDecay(-0.1)));
+        assert_eq!(orderbook.get_decay_imbalance(f64::INFINITY), Err(OrderbookError::InvalidDecay(f64::INFINITY)));
+        assert!(matches!(orderbook.get_decay_imbalance(f64::NAN), Err(OrderbookError::InvalidDecay(decay)) if decay.is_nan()));
+    }
+
+    #[test]
+    fn test_get_depth_profile_orderbook() {
+        let orderbook = signal_book();
+        let distances = [Distance::Absolute(0.5), Distance::Absolute(1.0), Distance::Bps(200.0), Distance::Absolute(10.0)];
+
+        let profile = orderbook.get_depth_profile(&distances).unwrap();
+        assert_eq!(profile.mid_price, 100.0);
+
+        let depths: Vec<(f64, f64)> = profile.points
+            .iter()
+            .map(|point| (point.bid_depth, point.ask_depth))
+            .collect();
+
+        assert_eq!(depths, vec![(0.0, 0.0), (1.0, 3.0), (4.0, 4.0), (4.0, 8.0)]);
+
+        assert_eq!(profile.points[2], DepthPoint {
+            distance: Distance::Bps(200.0),
+            bid_price: 98.0,
+            ask_price: 102.0,
+            bid_depth: 4.0,
+            ask_depth: 4.0
+        });
+
+        assert_eq!(profile.points[0].skew(), 0.0);
+        assert_eq!(profile.points[1].skew(), 1.0_f64.ln() - 3.0_f64.ln());
+        assert_eq!(orderbook.get_ordebook_skew_by_range(&10.0), Ok(profile.points[3].skew()));
+
+        let invalid = orderbook.get_depth_profile(&[Distance::Bps(-1.0)]);
+        assert_eq!(invalid, Err(OrderbookError::InvalidDistance(Distance::Bps(-1.0))));
+    }
+
+    #[test]
+    fn test_depth_profile_crossed_book_orderbook() {
+        let mut orderbook = Orderbook::new();
+        let bids = vec![Level { price: 101.0, size: 2.0 }, Level { price: 99.0, size: 1.0 }];
+        let asks = vec![Level { price: 100.0, size: 5.0 }, Level { price: 103.0, size: 1.0 }];
+
+        orderbook.apply_snapshot(&bids, &asks, 1, 1, 1);
+
+        // Mid is 100.5, the bid above it and the ask below it are not counted
+        let profile = orderbook.get_depth_profile(&[Distance::Absolute(1.0), Distance::Absolute(3.0)]).unwrap();
+
+        assert_eq!((profile.points[0].bid_depth, profile.points[0].ask_depth), (0.0, 0.0));
+        assert_eq!((profile.points[1].bid_depth, profile.points[1].ask_depth), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_levels_on_tick_grid_orderbook() {
+        let spec = InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap();
+        let mut orderbook = Orderbook::new().with_spec(spec.clone());
+
+        assert_eq!(orderbook.spec(), Some(&spec));
+
+        let bids = vec![Level { price: 0.1 + 0.2, size: 1.0 }];
+        let asks = vec![Level { price: 0.5, size: 0.1 + 0.2 }];
+        orderbook.apply_snapshot(&bids, &asks, 1, 1, 1);
+
+        assert_eq!(orderbook.get_bid().unwrap().price, 0.3);
+        assert_eq!(orderbook.get_ask().unwrap().size, 0.3);
+
+        // Without the grid 0.3 would be a second, phantom level next to 0.30000000000000004
+        orderbook.apply_delta(&[Level { price: 0.3, size: 2.0 }], &[], 2, 2, 2).unwrap();
+        assert_eq!(orderbook.get_bids().borrow().len(), 1);
+
+        orderbook.reduce_size(RestingOrderType::AskPrice(0.5), 0.1).unwrap();
+        assert_eq!(orderbook.get_ask().unwrap().size, 0.2);
+
+        orderbook.apply_delta(&[Level { price: 0.1 + 0.2, size: 0.0 }], &[], 3, 3, 3).unwrap();
+        assert!(orderbook.get_bids().borrow().is_empty());
+    }
+
+    #[test]
+    fn test_levels_without_spec_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        assert_eq!(orderbook.spec(), None);
+
+        // Keyed by ticks of the fallback grid, so float noise still lands on one level
+        orderbook.apply_snapshot(&[Level { price: 0.1 + 0.2, size: 1.0 }], &[Level { price: 0.5, size: 1.0 }], 1, 1, 1);
+        orderbook.apply_delta(&[Level { price: 0.3, size: 2.0 }], &[], 2, 2, 2).unwrap();
+
+        assert_eq!(orderbook.get_bids().borrow().len(), 1);
+        assert_eq!(orderbook.get_bid(), Some(RestingOrder::new(0.3, 2.0, 2)));
+
+        // A spec set later re-keys the levels already in the book
+        let orderbook = orderbook.with_spec(InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap());
+
+        assert_eq!(orderbook.get_bid(), Some(RestingOrder::new(0.3, 2.0, 2)));
+        assert_eq!(orderbook.get_ask(), Some(RestingOrder::new(0.5, 1.0, 1)));
+        assert_eq!(orderbook.last_update_time(), 2);
+    }
+
+    #[test]
+    fn test_sizes_below_one_lot_orderbook() {
+        let spec = InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap();
+        let mut orderbook = Orderbook::new().with_spec(spec);
+
+        let asks = vec![Level { price: 101.0, size: 0.0004 }, Level { price: 102.0, size: 1.0 }];
+        orderbook.apply_snapshot(&[Level { price: 99.0, size: 1.0 }], &asks, 1, 1, 1);
+
+        // Under half a lot rounds to nothing, so the level is never stored
+        assert_eq!(orderbook.get_asks().borrow().len(), 1);
+        assert_eq!(orderbook.get_ask().unwrap().price, 102.0);
+
+        let cost = orderbook.cost_to_fill(Side::Buy, 0.5).unwrap();
+        assert_eq!(cost.avg_price, 102.0);
+        assert_eq!(cost.levels, 1);
+
+        // An update that shrinks a level below one lot deletes it
+        orderbook.apply_delta(&[Level { price: 99.0, size: 0.0002 }], &[], 2, 2, 2).unwrap();
+        assert!(orderbook.get_bids().borrow().is_empty());
+
+        let removed = orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(102.0, 0.0001, 3))).unwrap();
+        assert_eq!(removed, Some(RestingOrder::new(102.0, 1.0, 1)));
+        assert!(orderbook.get_asks().borrow().is_empty());
+
+        let negative = orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(99.0, -1.0, 4)));
+        assert_eq!(negative, Err(OrderbookError::InvalidSize(-1.0)));
+    }
+
+    #[test]
+    fn test_prices_and_sizes_off_the_grid_orderbook() {
+        let mut orderbook = Orderbook::new();
+
+        let nan = orderbook.insert_order(RestingOrderType::BidOrder(RestingOrder::new(f64::NAN, 1.0, 1)));
+        assert!(matches!(nan, Err(OrderbookError::InvalidPrice(price)) if price.is_nan()));
+
+        // Too many fallback lots for an i64, rejected rather than stored at a saturated size
+        let huge = orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(0.5, 2e11, 1)));
+        assert_eq!(huge, Err(OrderbookError::InvalidSize(2e11)));
+        assert!(orderbook.get_bids().borrow().is_empty());
+        assert!(orderbook.get_asks().borrow().is_empty());
+
+        let mut orderbook = Orderbook::new().with_spec(InstrumentSpec::new("BTCUSDT", 0.5, 0.001).unwrap());
+
+        let infinite = orderbook.insert_order(RestingOrderType::AskOrder(RestingOrder::new(f64::INFINITY, 1.0, 1)));
+        assert_eq!(infinite, Err(OrderbookError::InvalidPrice(f64::INFINITY)));
+        assert_eq!(orderbook.remove_level(RestingOrderType::AskPrice(f64::INFINITY)), None);
+    }
 }
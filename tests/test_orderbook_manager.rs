@@ -0,0 +1,133 @@
+use std::time::Duration;
+use rust_workshop::instrument::InstrumentSpec;
+use rust_workshop::market::{ Category, Level };
+use rust_workshop::orderbook::{ OrderbookError, RestingOrder };
+use rust_workshop::orderbook_manager::{ BookKey, OrderbookManager, TopOfBook };
+use rust_workshop::public_stream::{ OrderbookUpdate, PublicEvent, UpdateKind };
+
+fn update(kind: UpdateKind, symbol: &str, bid: (f64, f64), ask: (f64, f64), update_id: u64) -> OrderbookUpdate {
+    OrderbookUpdate {
+        kind,
+        symbol: symbol.to_string(),
+        bids: vec![Level { price: bid.0, size: bid.1 }],
+        asks: vec![Level { price: ask.0, size: ask.1 }],
+        update_id,
+        seq: update_id,
+        ts: 1_700_000_000_000 + update_id
+    }
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_updates_orderbook_manager() {
+        let mut manager = OrderbookManager::new();
+
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "BTCUSDT", (30000.0, 1.0), (30001.0, 2.0), 1)).unwrap();
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "ETHUSDT", (2000.0, 5.0), (2000.5, 5.0), 7)).unwrap();
+
+        let event = PublicEvent::Orderbook(update(UpdateKind::Delta, "BTCUSDT", (30000.5, 1.0), (30001.0, 0.0), 2));
+        manager.apply_event(Category::Linear, &event).unwrap();
+
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.keys(), vec![BookKey::new(Category::Linear, "BTCUSDT"), BookKey::new(Category::Linear, "ETHUSDT")]);
+
+        let btc = manager.book(Category::Linear, "BTCUSDT").unwrap();
+        assert_eq!(btc.update_id(), Some(2));
+        assert_eq!(btc.get_bid().unwrap().price, 30000.5);
+        assert_eq!(btc.get_ask(), None);
+
+        let eth = manager.book(Category::Linear, "ETHUSDT").unwrap();
+        assert_eq!(eth.update_id(), Some(7));
+        assert_eq!(eth.get_mid_price(), Ok(2000.25));
+
+        assert!(manager.book(Category::Spot, "BTCUSDT").is_none());
+    }
+
+    #[test]
+    fn test_sync_state_orderbook_manager() {
+        let mut manager = OrderbookManager::new();
+        manager.track(Category::Linear, "SOLUSDT");
+
+        // Tracked books wait for their first snapshot
+        assert_eq!(manager.out_of_sync(), vec![BookKey::new(Category::Linear, "SOLUSDT")]);
+
+        let delta = update(UpdateKind::Delta, "SOLUSDT", (20.0, 1.0), (20.1, 1.0), 2);
+        assert_eq!(manager.apply(Category::Linear, &delta), Err(OrderbookError::NoSnapshot));
+
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "SOLUSDT", (20.0, 1.0), (20.1, 1.0), 1)).unwrap();
+        assert!(manager.out_of_sync().is_empty());
+
+        let gap = update(UpdateKind::Delta, "SOLUSDT", (20.0, 2.0), (20.1, 1.0), 5);
+        assert_eq!(manager.apply(Category::Linear, &gap), Err(OrderbookError::Gap { expected: 2, received: 5 }));
+
+        assert_eq!(manager.gaps(Category::Linear, "SOLUSDT"), 1);
+        assert_eq!(manager.out_of_sync(), vec![BookKey::new(Category::Linear, "SOLUSDT")]);
+
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "SOLUSDT", (20.0, 1.0), (20.1, 1.0), 9)).unwrap();
+        assert!(manager.out_of_sync().is_empty());
+
+        assert!(manager.remove(Category::Linear, "SOLUSDT").is_some());
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_staleness_orderbook_manager() {
+        let mut manager = OrderbookManager::new().with_max_age(Duration::from_millis(50));
+
+        manager.track(Category::Linear, "BTCUSDT");
+        assert_eq!(manager.age(Category::Linear, "BTCUSDT"), None);
+        assert_eq!(manager.stale_books(), vec![BookKey::new(Category::Linear, "BTCUSDT")]);
+
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "BTCUSDT", (30000.0, 1.0), (30001.0, 2.0), 1)).unwrap();
+        assert!(manager.stale_books().is_empty());
+
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(manager.age(Category::Linear, "BTCUSDT").unwrap() >= Duration::from_millis(50));
+        assert_eq!(manager.stale_books(), vec![BookKey::new(Category::Linear, "BTCUSDT")]);
+    }
+
+    #[test]
+    fn test_cross_book_queries_orderbook_manager() {
+        let mut manager = OrderbookManager::new();
+
+        manager.apply(Category::Spot, &update(UpdateKind::Snapshot, "BTCUSDT", (30000.0, 1.0), (30002.0, 1.0), 1)).unwrap();
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "BTCUSDT", (30001.0, 3.0), (30003.0, 3.0), 1)).unwrap();
+        manager.apply(Category::Linear, &update(UpdateKind::Snapshot, "ETHUSDT", (2000.0, 5.0), (2001.0, 5.0), 1)).unwrap();
+
+        let (category, bid) = manager.best_bid("BTCUSDT").unwrap();
+        assert_eq!((category, bid.price), (Category::Linear, 30001.0));
+
+        let (category, ask) = manager.best_ask("BTCUSDT").unwrap();
+        assert_eq!((category, ask.price), (Category::Spot, 30002.0));
+
+        assert_eq!(manager.best_bid("XRPUSDT"), None);
+
+        let tops = manager.top_of_book();
+        assert_eq!(tops.len(), 3);
+        assert_eq!(tops[2], TopOfBook {
+            key: BookKey::new(Category::Linear, "ETHUSDT"),
+            bid: Some(RestingOrder::new(2000.0, 5.0, 1_700_000_000_001)),
+            ask: Some(RestingOrder::new(2001.0, 5.0, 1_700_000_000_001))
+        });
+    }
+
+    #[test]
+    fn test_books_use_instrument_spec_orderbook_manager() {
+        let spec = InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap();
+        let mut manager = OrderbookManager::new().with_spec(Category::Linear, spec.clone());
+
+        manager.track(Category::Linear, "BTCUSDT");
+        manager.track(Category::Spot, "BTCUSDT");
+
+        assert_eq!(manager.book(Category::Linear, "BTCUSDT").unwrap().spec(), Some(&spec));
+        assert_eq!(manager.book(Category::Spot, "BTCUSDT").unwrap().spec(), None);
+    }
+}
@@ -0,0 +1,174 @@
+use serde_json::json;
+use rust_workshop::executor::{ decode_response, ApiResponse, ExecutorError };
+use rust_workshop::instrument::{ InstrumentSpec, Price, Qty, Rounding };
+use rust_workshop::market::Side;
+use rust_workshop::oms::{ Oms, OrderState };
+use rust_workshop::orders::{
+    batch_results, AmendOrderRequest, CancelOrderRequest, OrderAck, OrderAckList, OrderRef, PlaceOrderRequest, TimeInForce
+};
+
+// Ticks of 0.1 and lots of 0.001
+fn btcusdt() -> InstrumentSpec {
+    InstrumentSpec::new("BTCUSDT", 0.1, 0.001).unwrap()
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place_limit_order_request_orders() {
+        let request = PlaceOrderRequest::limit(&btcusdt(), Side::Buy, Qty(10), Price(300005))
+            .time_in_force(TimeInForce::PostOnly)
+            .order_link_id("quote-1")
+            .position_idx(1);
+
+        assert_eq!(serde_json::to_value(&request).unwrap(), json!({
+            "symbol": "BTCUSDT",
+            "side": "Buy",
+            "orderType": "Limit",
+            "qty": "0.010",
+            "price": "30000.5",
+            "timeInForce": "PostOnly",
+            "orderLinkId": "quote-1",
+            "positionIdx": 1
+        }));
+    }
+
+    #[test]
+    fn test_place_market_order_request_orders() {
+        let ethusdt = InstrumentSpec::new("ETHUSDT", 0.01, 0.01).unwrap();
+        let request = PlaceOrderRequest::market(&ethusdt, Side::Sell, Qty(200))
+            .time_in_force(TimeInForce::IOC)
+            .reduce_only();
+
+        assert_eq!(serde_json::to_value(&request).unwrap(), json!({
+            "symbol": "ETHUSDT",
+            "side": "Sell",
+            "orderType": "Market",
+            "qty": "2.00",
+            "timeInForce": "IOC",
+            "reduceOnly": true
+        }));
+    }
+
+    #[test]
+    fn test_amend_and_cancel_request_orders() {
+        let amend = AmendOrderRequest::new(&btcusdt(), OrderRef::OrderLinkId("quote-1".to_string()))
+            .price(Price(300010));
+
+        assert_eq!(serde_json::to_value(&amend).unwrap(), json!({
+            "symbol": "BTCUSDT",
+            "orderLinkId": "quote-1",
+            "price": "30001.0"
+        }));
+
+        let cancel = CancelOrderRequest::new("BTCUSDT", OrderRef::OrderId("1321003749386327552".to_string()));
+
+        assert_eq!(serde_json::to_value(&cancel).unwrap(), json!({
+            "symbol": "BTCUSDT",
+            "orderId": "1321003749386327552"
+        }));
+    }
+
+    #[test]
+    fn test_decode_order_ack_orders() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": { "orderId": "1321003749386327552", "orderLinkId": "spot-test-postonly" },
+            "retExtInfo": {},
+            "time": 1672211918471
+        }"#;
+
+        let response: ApiResponse<OrderAck> = decode_response(200, body).unwrap();
+
+        assert_eq!(response.result.order_id, "1321003749386327552");
+        assert_eq!(response.result.order_link_id, "spot-test-postonly");
+    }
+
+    #[test]
+    fn test_batch_results_orders() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "list": [
+                    { "category": "linear", "symbol": "BTCUSDT", "orderId": "b-1", "orderLinkId": "q-1", "createAt": "1676355133123" },
+                    { "category": "linear", "symbol": "BTCUSDT", "orderId": "", "orderLinkId": "q-2", "createAt": "" }
+                ]
+            },
+            "retExtInfo": {
+                "list": [
+                    { "code": 0, "msg": "OK" },
+                    { "code": 110007, "msg": "ab not enough for new order" }
+                ]
+            },
+            "time": 1676355133126
+        }"#;
+
+        let response: ApiResponse<OrderAckList> = decode_response(200, body).unwrap();
+        let results = batch_results(response.result.list, response.ret_ext_info).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().order_id, "b-1");
+        assert!(matches!(results[1], Err(ExecutorError::Exchange { code: 110007, .. })));
+
+        // One status short, the acks can't be matched to their statuses
+        let response: ApiResponse<OrderAckList> = decode_response(200, body).unwrap();
+        let ret_ext_info = serde_json::json!({ "list": [{ "code": 0, "msg": "OK" }] });
+
+        assert!(matches!(batch_results(response.result.list, ret_ext_info), Err(ExecutorError::Decode(_))));
+    }
+
+    #[test]
+    fn test_to_pending_order_orders() {
+        let mut oms = Oms::new();
+
+        let request = PlaceOrderRequest::limit(&btcusdt(), Side::Sell, Qty(500), Price(310000));
+        let ack = OrderAck { order_id: "1234".to_string(), order_link_id: String::new() };
+
+        oms.add(Side::Sell, OrderState::PendingNew, request.to_pending_order(&ack, 1_000)).unwrap();
+
+        let order = oms.get("1234").unwrap();
+
+        assert_eq!(order.state, OrderState::PendingNew);
+        assert_eq!(order.symbol(), "BTCUSDT");
+        assert_eq!(order.price, Price(310000));
+        assert_eq!(order.qty, Qty(500));
+        assert_eq!(order.leaves_qty, Qty(500));
+        assert_eq!(order.created_time, 1_000);
+    }
+
+    #[test]
+    fn test_fixed_point_request_orders() {
+        let spec = btcusdt();
+
+        // Float noise never reaches the wire and bids never round up into the spread
+        let qty = spec.order_qty(0.1 + 0.2).unwrap();
+        let buy = PlaceOrderRequest::limit(&spec, Side::Buy, qty, spec.bid_price(30000.17).unwrap());
+        let sell = PlaceOrderRequest::limit(&spec, Side::Sell, spec.order_qty(0.0129).unwrap(), spec.ask_price(30000.11).unwrap());
+
+        assert_eq!(serde_json::to_value(&buy).unwrap()["qty"], "0.300");
+        assert_eq!(serde_json::to_value(&buy).unwrap()["price"], "30000.1");
+        assert_eq!(serde_json::to_value(&sell).unwrap()["qty"], "0.012");
+        assert_eq!(serde_json::to_value(&sell).unwrap()["price"], "30000.2");
+
+        assert_eq!(buy, PlaceOrderRequest::limit(&spec, Side::Buy, Qty(300), Price(300001)));
+
+        let amend = AmendOrderRequest::new(&spec, OrderRef::OrderLinkId("quote-1".to_string()))
+            .price(spec.price(30000.16, Rounding::Nearest).unwrap())
+            .qty(Qty(12));
+
+        assert_eq!(serde_json::to_value(&amend).unwrap(), json!({
+            "symbol": "BTCUSDT",
+            "orderLinkId": "quote-1",
+            "qty": "0.012",
+            "price": "30000.2"
+        }));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use serde_json::json;
+use rust_workshop::fees::{ FeeRate, FeeSchedule };
+use rust_workshop::market::{ Level, Side };
+use rust_workshop::orderbook::Orderbook;
+use rust_workshop::position::{ CostBasis, Fill, FundingPayment, PnlBreakdown, Position, PositionKeeper };
+use rust_workshop::private_stream::parse_private;
+
+fn fill(side: Side, qty: f64, price: f64) -> Fill {
+    Fill::new("BTCUSDT", side, qty, price, 1)
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_position() {
+        let mut position = Position::new("BTCUSDT", CostBasis::Fifo);
+
+        assert_eq!(position.apply_fill(&fill(Side::Buy, 1.0, 100.0)).trading, 0.0);
+        assert_eq!(position.apply_fill(&fill(Side::Buy, 1.0, 110.0)).trading, 0.0);
+        assert_eq!(position.avg_entry_price(), Some(105.0));
+
+        // Closes all of the 100 lot then half of the 110 lot
+        assert_eq!(position.apply_fill(&fill(Side::Sell, 1.5, 120.0)).trading, 25.0);
+
+        assert_eq!(position.net_qty(), 0.5);
+        assert_eq!(position.side(), Some(Side::Buy));
+        assert_eq!(position.avg_entry_price(), Some(110.0));
+        assert_eq!(position.realized_pnl(), 25.0);
+        assert_eq!(position.unrealized_pnl(130.0), 10.0);
+    }
+
+    #[test]
+    fn test_average_cost_position() {
+        let mut position = Position::new("BTCUSDT", CostBasis::AverageCost);
+
+        position.apply_fill(&fill(Side::Buy, 1.0, 100.0));
+        position.apply_fill(&fill(Side::Buy, 1.0, 110.0));
+
+        assert_eq!(position.apply_fill(&fill(Side::Sell, 1.5, 120.0)).trading, 22.5);
+
+        assert_eq!(position.net_qty(), 0.5);
+        assert_eq!(position.avg_entry_price(), Some(105.0));
+        assert_eq!(position.unrealized_pnl(130.0), 12.5);
+    }
+
+    #[test]
+    fn test_flip_and_flat_position() {
+        let mut position = Position::new("BTCUSDT", CostBasis::AverageCost);
+
+        position.apply_fill(&fill(Side::Buy, 1.0, 100.0));
+
+        // Sells through the long and opens a short at the fill price
+        assert_eq!(position.apply_fill(&fill(Side::Sell, 3.0, 90.0)).trading, -10.0);
+        assert_eq!(position.net_qty(), -2.0);
+        assert_eq!(position.side(), Some(Side::Sell));
+        assert_eq!(position.avg_entry_price(), Some(90.0));
+        assert_eq!(position.unrealized_pnl(80.0), 20.0);
+
+        assert_eq!(position.apply_fill(&fill(Side::Buy, 2.0, 85.0)).trading, 10.0);
+        assert!(position.is_flat());
+        assert_eq!(position.avg_entry_price(), None);
+        assert_eq!(position.unrealized_pnl(80.0), 0.0);
+        assert_eq!(position.realized_pnl(), 0.0);
+    }
+
+    #[test]
+    fn test_unrealized_pnl_at_mid_position() {
+        let mut position = Position::new("BTCUSDT", CostBasis::Fifo);
+        let mut orderbook = Orderbook::new();
+
+        position.apply_fill(&fill(Side::Sell, 2.0, 100.0));
+
+        assert!(position.unrealized_pnl_at_mid(&orderbook).is_err());
+
+        orderbook.apply_snapshot(&[Level { price: 95.0, size: 1.0 }], &[Level { price: 97.0, size: 1.0 }], 1, 1, 1);
+        assert_eq!(position.unrealized_pnl_at_mid(&orderbook), Ok(8.0));
+    }
+
+    #[test]
+    fn test_keeper_consumes_executions_position() {
+        let mut keeper = PositionKeeper::new(CostBasis::Fifo);
+
+        let executions = json!({
+            "topic": "execution",
+            "id": "1",
+            "creationTime": 1746270400355u64,
+            "data": [
+                {
+                    "category": "linear", "symbol": "BTCUSDT", "isMaker": true, "side": "Buy",
+                    "orderId": "a", "orderLinkId": "", "execFee": "-0.25", "execId": "e-1",
+                    "execPrice": "30000", "execQty": "0.2", "execType": "Trade",
+                    "execTime": "1746270400353", "leavesQty": "0"
+                },
+                {
+                    "category": "linear", "symbol": "ETHUSDT", "isMaker": false, "side": "Sell",
+                    "orderId": "b", "orderLinkId": "", "execFee": "0.75", "execId": "e-2",
+                    "execPrice": "2000", "execQty": "1", "execType": "Trade",
+                    "execTime": "1746270400354", "leavesQty": "0"
+                },
+                {
+                    "category": "linear", "symbol": "BTCUSDT", "isMaker": false, "side": "Sell",
+                    "orderId": "", "orderLinkId": "", "execFee": "0.5", "execId": "e-3",
+                    "execPrice": "30100", "execQty": "0.2", "execType": "Funding",
+                    "execTime": "1746270400355", "leavesQty": "0"
+                },
+                {
+                    "category": "spot", "symbol": "SOLUSDT", "isMaker": false, "side": "Buy",
+                    "orderId": "c", "orderLinkId": "", "execFee": "0.001", "execId": "e-4",
+                    "execPrice": "150", "execQty": "1", "execType": "Trade",
+                    "execTime": "1746270400356", "leavesQty": "0"
+                }
+            ]
+        });
+
+        keeper.apply_private_event(&parse_private(executions).unwrap().unwrap());
+
+        // Funding doesn't change the position, only realized PnL. The spot buy is skipped,
+        // its fee is in SOL
+        assert_eq!(keeper.net_qty("BTCUSDT"), 0.2);
+        assert_eq!(keeper.net_qty("ETHUSDT"), -1.0);
+        assert_eq!(keeper.net_qty("SOLUSDT"), 0.0);
+        assert!(keeper.position("SOLUSDT").is_none());
+        assert_eq!(keeper.position("BTCUSDT").unwrap().last_fill_time(), 1746270400353);
+
+        assert_eq!(keeper.position("BTCUSDT").unwrap().pnl_breakdown(), PnlBreakdown { trading: 0.0, fees: -0.25, funding: 0.5 });
+
+        keeper.apply_fill(&Fill::new("BTCUSDT", Side::Sell, 0.1, 31000.0, 1746270400400).with_fee(0.0));
+        assert_eq!(keeper.realized_pnl(), 99.0);
+
+        let marks = HashMap::from([("BTCUSDT".to_string(), 30500.0), ("ETHUSDT".to_string(), 1900.0)]);
+        assert_eq!(keeper.unrealized_pnl(&marks), 150.0);
+    }
+
+    #[test]
+    fn test_liquidation_flattens_position() {
+        let mut keeper = PositionKeeper::new(CostBasis::Fifo);
+
+        keeper.apply_fill(&fill(Side::Buy, 0.5, 30000.0).with_fee(0.0));
+
+        let liquidation = json!({
+            "topic": "execution",
+            "id": "2",
+            "creationTime": 1746270400500u64,
+            "data": [
+                {
+                    "category": "linear", "symbol": "BTCUSDT", "isMaker": false, "side": "Sell",
+                    "orderId": "liq", "orderLinkId": "", "execFee": "3", "execId": "e-5",
+                    "execPrice": "28000", "execQty": "0.5", "execType": "BustTrade",
+                    "execTime": "1746270400500", "leavesQty": "0"
+                }
+            ]
+        });
+
+        keeper.apply_private_event(&parse_private(liquidation).unwrap().unwrap());
+
+        let btc = keeper.position("BTCUSDT").unwrap();
+        assert!(btc.is_flat());
+        assert_eq!(btc.last_fill_time(), 1746270400500);
+        assert_eq!(btc.pnl_breakdown(), PnlBreakdown { trading: -1000.0, fees: 3.0, funding: 0.0 });
+    }
+
+    #[test]
+    fn test_fees_and_funding_position() {
+        let schedule = FeeSchedule::new(FeeRate::new(0.0002, 0.0005))
+            .with_rate("BTCUSDT", FeeRate::new(-0.0001, 0.0005));
+
+        let mut keeper = PositionKeeper::new(CostBasis::Fifo).with_fee_schedule(schedule);
+
+        let day = 86_400_000;
+
+        // Maker rebate of 0.5 on the way in, taker fee of 2.5 on the way out
+        let entry = keeper.apply_fill(&fill(Side::Buy, 0.1, 50000.0).with_maker(true));
+        assert_eq!(entry, PnlBreakdown { trading: 0.0, fees: -0.5, funding: 0.0 });
+        assert_eq!(entry.net(), 0.5);
+
+        let funding = keeper.apply_funding(&FundingPayment::new("BTCUSDT", 1.5, day + 1));
+        assert_eq!(funding, PnlBreakdown { trading: 0.0, fees: 0.0, funding: 1.5 });
+
+        let exit = Fill::new("BTCUSDT", Side::Sell, 0.1, 50000.0, day + 2);
+        assert_eq!(keeper.apply_fill(&exit), PnlBreakdown { trading: 0.0, fees: 2.5, funding: 0.0 });
+
+        // A fee sent with the fill wins over the schedule
+        let eth = Fill::new("ETHUSDT", Side::Buy, 1.0, 2000.0, day + 3).with_fee(0.25);
+        assert_eq!(keeper.apply_fill(&eth).fees, 0.25);
+
+        let btc = keeper.position("BTCUSDT").unwrap();
+        assert!(btc.is_flat());
+        assert_eq!(btc.pnl_breakdown(), PnlBreakdown { trading: 0.0, fees: 2.0, funding: 1.5 });
+        assert_eq!(btc.realized_pnl(), -3.5);
+
+        assert_eq!(keeper.realized_pnl(), -3.75);
+
+        let daily: Vec<(u64, PnlBreakdown)> = keeper.daily_pnl().iter().map(|(day, pnl)| (*day, *pnl)).collect();
+
+        assert_eq!(daily, vec![
+            (0, PnlBreakdown { trading: 0.0, fees: -0.5, funding: 0.0 }),
+            (day, PnlBreakdown { trading: 0.0, fees: 2.75, funding: 1.5 })
+        ]);
+        assert_eq!(daily[1].1.net(), -4.25);
+    }
+}
@@ -0,0 +1,335 @@
+use url::Url;
+use std::time::Duration;
+use serde_json::{ json, Value };
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::UnboundedReceiver;
+use futures_util::{ SinkExt, StreamExt };
+use tokio_tungstenite::{ accept_async, tungstenite::Message };
+use rust_workshop::executor::Executor;
+use rust_workshop::instrument::{ InstrumentSpec, Price, Qty };
+use rust_workshop::market::Side;
+use rust_workshop::oms::{ Oms, OmsError, Order, OrderState };
+use rust_workshop::private_stream::{ parse_private, OrderStatus, PrivateEvent, PrivateStream, PrivateTopic };
+use rust_workshop::websocket::{ StreamConfig, StreamEvent };
+
+fn executor() -> Executor {
+    let url = Url::parse("https://api-testnet.bybit.com").unwrap();
+
+    Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+}
+
+// Ticks and lots of 0.01, the order in order_message is 125000 ticks for 15 lots
+fn ethusdt() -> InstrumentSpec {
+    InstrumentSpec::new("ETHUSDT", 0.01, 0.01).unwrap()
+}
+
+fn order_message(status: &str, leaves_qty: &str, cum_exec_qty: &str, updated_time: u64) -> Value {
+    json!({
+        "id": "5923240c6880ab-c59f-420b-9adb-3639adc9dd90",
+        "topic": "order",
+        "creationTime": 1672364262474u64,
+        "data": [{
+            "symbol": "ETHUSDT",
+            "orderId": "5cf98598-39a7-459e-97bf-76ca765ee020",
+            "side": "Sell",
+            "orderType": "Limit",
+            "cancelType": "UNKNOWN",
+            "price": "1250.00",
+            "qty": "0.15",
+            "orderIv": "",
+            "timeInForce": "GTC",
+            "orderStatus": status,
+            "orderLinkId": "quote-1",
+            "lastPriceOnCreated": "",
+            "reduceOnly": false,
+            "leavesQty": leaves_qty,
+            "leavesValue": "0",
+            "cumExecQty": cum_exec_qty,
+            "cumExecValue": "0",
+            "avgPrice": "",
+            "blockTradeId": "",
+            "positionIdx": 0,
+            "cumExecFee": "0",
+            "createdTime": "1672364262444",
+            "updatedTime": updated_time.to_string(),
+            "rejectReason": "EC_NoError",
+            "category": "linear"
+        }]
+    })
+}
+
+async fn next_event(events: &mut UnboundedReceiver<StreamEvent<PrivateEvent>>) -> StreamEvent<PrivateEvent> {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("Timed out waiting for a stream event")
+        .expect("Stream closed")
+}
+
+// Accepts one connection, answers the auth frame and reports every frame it receives
+async fn private_server(auth_success: bool, push: Vec<Value>) -> (Url, UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+    let (frames_tx, frames) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(socket).await.unwrap();
+
+        while let Some(Ok(Message::Text(frame))) = ws.next().await {
+            let frame: Value = serde_json::from_str(&frame).unwrap();
+            let op = frame["op"].as_str().unwrap().to_string();
+            frames_tx.send(frame).unwrap();
+
+            if op == "auth" {
+                let ack = json!({ "success": auth_success, "ret_msg": if auth_success { "" } else { "Invalid sign" }, "op": "auth", "conn_id": "1" });
+                ws.send(Message::Text(ack.to_string())).await.unwrap();
+            }
+
+            if op == "subscribe" {
+                for message in &push {
+                    ws.send(Message::Text(message.to_string())).await.unwrap();
+                }
+            }
+        }
+    });
+
+    (url, frames)
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_frame_private_stream() {
+        let frame: Value = serde_json::from_str(&executor().realtime_auth().auth_frame(1662350400000)).unwrap();
+
+        assert_eq!(frame, json!({
+            "op": "auth",
+            "args": ["key", 1662350400000u64, "d7ca36fea9ef1287007fd4b15af961e91d419a3d3f3ccbdf23585170ac116cd4"]
+        }));
+
+        assert_eq!(PrivateTopic::Execution.to_string(), "execution");
+        assert_eq!(PrivateTopic::Wallet.to_string(), "wallet");
+    }
+
+    #[test]
+    fn test_parse_order_and_execution_private_stream() {
+        let Some(PrivateEvent::Orders(orders)) = parse_private(order_message("New", "0.15", "0", 1672364262444)).unwrap() else {
+            panic!("Expected orders")
+        };
+
+        assert_eq!(orders[0].order_status, OrderStatus::New);
+        assert_eq!(orders[0].side, Side::Sell);
+        assert_eq!(orders[0].price, 1250.0);
+        assert_eq!(orders[0].avg_price, None);
+        assert_eq!(orders[0].created_time, 1672364262444);
+
+        let execution = json!({
+            "topic": "execution.linear",
+            "id": "386825804_BTCUSDT_140612148849382",
+            "creationTime": 1746270400355u64,
+            "data": [{
+                "category": "linear", "symbol": "BTCUSDT", "isMaker": false, "side": "Sell",
+                "orderId": "fd4300ae-7847-404e-b947-b46980a4d140", "orderLinkId": "",
+                "execFee": "0.0552", "execId": "e0cbe81d-0f18-5866-9415-cf319b5dab3b",
+                "execPrice": "95900.1", "execQty": "0.001", "execType": "Trade",
+                "execTime": "1746270400353", "leavesQty": "0", "feeRate": "0.00055"
+            }]
+        });
+
+        let Some(PrivateEvent::Executions(executions)) = parse_private(execution).unwrap() else { panic!("Expected executions") };
+        assert_eq!(executions[0].exec_qty, 0.001);
+        assert_eq!(executions[0].exec_time, 1746270400353);
+        assert!(!executions[0].is_maker);
+    }
+
+    #[test]
+    fn test_parse_position_and_wallet_private_stream() {
+        let position = json!({
+            "id": "1003076014fb7eedb-c7e6-45d6-a8c1-270f0169171a",
+            "topic": "position",
+            "creationTime": 1697682317044u64,
+            "data": [{
+                "positionIdx": 0, "tradeMode": 0, "riskId": 1, "riskLimitValue": "2000000",
+                "symbol": "BTCUSDT", "side": "", "size": "0", "entryPrice": "0", "leverage": "10",
+                "markPrice": "28184.5", "unrealisedPnl": "0", "cumRealisedPnl": "-0.4",
+                "updatedTime": "1697682317038", "category": "linear"
+            }]
+        });
+
+        let Some(PrivateEvent::Positions(positions)) = parse_private(position).unwrap() else { panic!("Expected positions") };
+        assert_eq!(positions[0].side, None);
+        assert_eq!(positions[0].cum_realised_pnl, Some(-0.4));
+
+        let wallet = json!({
+            "id": "592324d2bce751-ad38-48eb-8f42-4671d1fb4d4e",
+            "topic": "wallet",
+            "creationTime": 1700034722104u64,
+            "data": [{
+                "accountType": "UNIFIED", "totalEquity": "10.1", "totalWalletBalance": "10",
+                "totalAvailableBalance": "9.5",
+                "coin": [{ "coin": "USDT", "equity": "10.1", "walletBalance": "10", "unrealisedPnl": "0.1", "cumRealisedPnl": "" }]
+            }]
+        });
+
+        let Some(PrivateEvent::Wallet(wallet)) = parse_private(wallet).unwrap() else { panic!("Expected wallet") };
+        assert_eq!(wallet[0].total_equity, Some(10.1));
+        assert_eq!(wallet[0].coin[0].cum_realised_pnl, None);
+    }
+
+    #[test]
+    fn test_oms_follows_order_lifecycle_private_stream() {
+        let mut oms = Oms::new().with_instrument(ethusdt());
+        let order_id = "5cf98598-39a7-459e-97bf-76ca765ee020".to_string();
+
+        let pending = Order::new(&ethusdt(), order_id.clone(), Side::Sell, Price(125000), Qty(15), 1672364262400, 1672364262400)
+            .with_state(OrderState::PendingNew);
+        oms.add(Side::Sell, OrderState::PendingNew, pending).unwrap();
+
+        let apply = |oms: &mut Oms, message: Value| {
+            oms.apply_private_event(&parse_private(message).unwrap().unwrap())
+        };
+
+        apply(&mut oms, order_message("New", "0.15", "0", 1672364262444)).unwrap();
+        assert!(oms.sell_side_orders_pending().borrow().is_empty());
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].state, OrderState::Active);
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].leaves_qty, Qty(15));
+
+        apply(&mut oms, order_message("PartiallyFilled", "0.05", "0.1", 1672364262500)).unwrap();
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].leaves_qty, Qty(5));
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].cum_filled_qty, Qty(10));
+
+        // A late New must not undo the partial fill
+        apply(&mut oms, order_message("New", "0.15", "0", 1672364262444)).unwrap();
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].state, OrderState::PartiallyFilled);
+        assert_eq!(oms.sell_side_orders_active().borrow()[&order_id].leaves_qty, Qty(5));
+
+        apply(&mut oms, order_message("Filled", "0", "0.15", 1672364262600)).unwrap();
+        assert!(oms.sell_side_orders_active().borrow().is_empty());
+        assert_eq!(oms.get_inventory_delta(), 0.0);
+
+        // A newer update that would take a partially filled order back to New is an invalid transition
+        let partially_filled = Order::new(&ethusdt(), order_id.clone(), Side::Sell, Price(125000), Qty(15), 0, 0).with_state(OrderState::PartiallyFilled);
+        oms.add(Side::Sell, OrderState::PartiallyFilled, partially_filled).unwrap();
+
+        let error = apply(&mut oms, order_message("New", "0.15", "0", 1672364262700)).unwrap_err();
+        assert_eq!(error, OmsError::InvalidTransition { id: order_id.clone(), from: OrderState::PartiallyFilled, to: OrderState::Active });
+    }
+
+    #[test]
+    fn test_oms_ignores_stale_updates_after_close_private_stream() {
+        let mut oms = Oms::new().with_instrument(ethusdt());
+        let order_id = "5cf98598-39a7-459e-97bf-76ca765ee020";
+
+        let apply = |oms: &mut Oms, message: Value| {
+            oms.apply_private_event(&parse_private(message).unwrap().unwrap())
+        };
+
+        apply(&mut oms, order_message("New", "0.15", "0", 1)).unwrap();
+        apply(&mut oms, order_message("Filled", "0", "0.15", 3)).unwrap();
+
+        assert_eq!(oms.get(order_id), Err(OmsError::UnknownOrder(order_id.to_string())));
+        assert_eq!(oms.closed_state(order_id), Some(OrderState::Filled));
+
+        // Delivered late, it must not bring the filled order back as a working one
+        apply(&mut oms, order_message("PartiallyFilled", "0.05", "0.1", 2)).unwrap();
+        apply(&mut oms, order_message("New", "0.15", "0", 3)).unwrap();
+
+        assert!(oms.orders_by_symbol("ETHUSDT").is_empty());
+        assert_eq!(oms.get_inventory_delta(), 0.0);
+
+        // A newer update can't reopen it either
+        let error = apply(&mut oms, order_message("PartiallyFilled", "0.05", "0.1", 4)).unwrap_err();
+        assert_eq!(error, OmsError::Closed { id: order_id.to_string(), state: OrderState::Filled });
+        assert!(oms.orders_by_symbol("ETHUSDT").is_empty());
+
+        // Once pruned the id is forgotten
+        oms.prune_closed(4);
+        assert_eq!(oms.closed_state(order_id), None);
+    }
+
+    #[test]
+    fn test_oms_matches_by_link_id_private_stream() {
+        let mut oms = Oms::new();
+
+        // Tracked under a local id before the exchange assigned one
+        let pending = Order::new(&ethusdt(), "local-1".to_string(), Side::Sell, Price(125000), Qty(15), 0, 0)
+            .with_order_link_id("quote-1")
+            .with_state(OrderState::PendingNew);
+        oms.add(Side::Sell, OrderState::PendingNew, pending).unwrap();
+
+        oms.apply_private_event(&parse_private(order_message("New", "0.15", "0", 1672364262444)).unwrap().unwrap()).unwrap();
+
+        let order = oms.get("5cf98598-39a7-459e-97bf-76ca765ee020").unwrap();
+
+        assert_eq!(order.state, OrderState::Active);
+        assert_eq!(order.symbol(), "ETHUSDT");
+        assert_eq!(order.price, Price(125000));
+        assert_eq!(oms.get_by_link_id("quote-1"), Ok(order));
+        assert_eq!(oms.get("local-1"), Err(OmsError::UnknownOrder("local-1".to_string())));
+        assert_eq!(oms.orders_by_symbol("ETHUSDT").len(), 1);
+    }
+
+    #[test]
+    fn test_oms_confirms_order_still_new_private_stream() {
+        let mut oms = Oms::new().with_instrument(ethusdt());
+        let order_id = "5cf98598-39a7-459e-97bf-76ca765ee020";
+
+        // Sent, but the stream got there before the caller marked it PendingNew
+        let new = Order::new(&ethusdt(), order_id.to_string(), Side::Sell, Price(125000), Qty(15), 0, 0);
+        oms.add(Side::Sell, OrderState::New, new).unwrap();
+
+        oms.apply_private_event(&parse_private(order_message("New", "0.15", "0", 1672364262444)).unwrap().unwrap()).unwrap();
+        assert_eq!(oms.get(order_id).unwrap().state, OrderState::Active);
+
+        // A first update that is already a fill works the same way
+        let mut oms = Oms::new().with_instrument(ethusdt());
+        let new = Order::new(&ethusdt(), order_id.to_string(), Side::Sell, Price(125000), Qty(15), 0, 0);
+        oms.add(Side::Sell, OrderState::New, new).unwrap();
+
+        oms.apply_private_event(&parse_private(order_message("PartiallyFilled", "0.05", "0.1", 1672364262500)).unwrap().unwrap()).unwrap();
+
+        let order = oms.get(order_id).unwrap();
+
+        assert_eq!(order.state, OrderState::PartiallyFilled);
+        assert_eq!(order.leaves_qty, Qty(5));
+    }
+
+    #[tokio::test]
+    async fn test_stream_authenticates_before_subscribing_private_stream() {
+        let (url, mut frames) = private_server(true, vec![order_message("New", "0.15", "0", 1672364262444)]).await;
+        let topics = [PrivateTopic::Order, PrivateTopic::Execution];
+
+        let (handle, mut events) = PrivateStream::connect(&executor(), url, &topics, StreamConfig::default());
+
+        assert_eq!(next_event(&mut events).await, StreamEvent::Connected);
+        assert!(matches!(next_event(&mut events).await, StreamEvent::Message(PrivateEvent::Orders(_))));
+
+        let auth = frames.recv().await.unwrap();
+        assert_eq!(auth["op"], "auth");
+        assert_eq!(auth["args"][0], "key");
+
+        let subscribe = frames.recv().await.unwrap();
+        assert_eq!(subscribe, json!({ "op": "subscribe", "args": ["order", "execution"] }));
+
+        handle.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_failed_auth_private_stream() {
+        let (url, mut frames) = private_server(false, vec![]).await;
+
+        let (handle, mut events) = PrivateStream::connect(&executor(), url, &[PrivateTopic::Order], StreamConfig::default());
+
+        assert_eq!(next_event(&mut events).await, StreamEvent::Error("auth failed: Invalid sign".to_string()));
+        assert_eq!(next_event(&mut events).await, StreamEvent::Disconnected);
+        assert_eq!(frames.recv().await.unwrap()["op"], "auth");
+
+        handle.close().await;
+    }
+}
@@ -0,0 +1,206 @@
+use url::Url;
+use std::time::Duration;
+use serde_json::{ json, Value };
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::UnboundedReceiver;
+use futures_util::{ SinkExt, StreamExt };
+use tokio_tungstenite::{ accept_async, tungstenite::Message };
+use rust_workshop::market::{ Category, Interval, Side };
+use rust_workshop::orderbook::Orderbook;
+use rust_workshop::public_stream::{ parse_public, public_url, PublicEvent, PublicStream, Topic, UpdateKind, MAINNET_PUBLIC_URL };
+use rust_workshop::websocket::{ StreamConfig, StreamEvent };
+
+const SNAPSHOT: &str = r#"{
+    "topic": "orderbook.50.BTCUSDT",
+    "type": "snapshot",
+    "ts": 1672304484978,
+    "data": {
+        "s": "BTCUSDT",
+        "b": [["16493.50", "0.006"], ["16493.00", "0.100"]],
+        "a": [["16611.00", "0.029"], ["16612.00", "0.213"]],
+        "u": 18521288,
+        "seq": 7961638724
+    },
+    "cts": 1672304484976
+}"#;
+
+const DELTA: &str = r#"{
+    "topic": "orderbook.50.BTCUSDT",
+    "type": "delta",
+    "ts": 1672304484990,
+    "data": {
+        "s": "BTCUSDT",
+        "b": [["16493.50", "0"], ["16490.00", "1.5"]],
+        "a": [["16611.00", "0.5"]],
+        "u": 18521289,
+        "seq": 7961638725
+    },
+    "cts": 1672304484988
+}"#;
+
+fn fast_config() -> StreamConfig {
+    StreamConfig {
+        reconnect_delay: Duration::from_millis(10),
+        ..StreamConfig::default()
+    }
+}
+
+async fn next_event(events: &mut UnboundedReceiver<StreamEvent<PublicEvent>>) -> StreamEvent<PublicEvent> {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("Timed out waiting for a stream event")
+        .expect("Stream closed")
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_strings_public_stream() {
+        let topics = [
+            Topic::Orderbook { depth: 50, symbol: "BTCUSDT".to_string() },
+            Topic::PublicTrade { symbol: "BTCUSDT".to_string() },
+            Topic::Tickers { symbol: "BTCUSDT".to_string() },
+            Topic::Kline { interval: Interval::Hour1, symbol: "BTCUSDT".to_string() }
+        ];
+
+        let strings: Vec<String> = topics.iter().map(Topic::to_string).collect();
+
+        assert_eq!(strings, vec!["orderbook.50.BTCUSDT", "publicTrade.BTCUSDT", "tickers.BTCUSDT", "kline.60.BTCUSDT"]);
+        assert_eq!(public_url(MAINNET_PUBLIC_URL, Category::Linear).as_str(), "wss://stream.bybit.com/v5/public/linear");
+    }
+
+    #[test]
+    fn test_parse_orderbook_snapshot_public_stream() {
+        let event = parse_public(serde_json::from_str(SNAPSHOT).unwrap()).unwrap().unwrap();
+
+        let PublicEvent::Orderbook(update) = event else { panic!("Expected an orderbook event") };
+
+        assert_eq!(update.kind, UpdateKind::Snapshot);
+        assert_eq!(update.symbol, "BTCUSDT");
+        assert_eq!(update.bids.len(), 2);
+        assert_eq!(update.asks[0].price, 16611.0);
+        assert_eq!(update.update_id, 18521288);
+        assert_eq!(update.seq, 7961638724);
+        assert_eq!(update.ts, 1672304484978);
+    }
+
+    #[test]
+    fn test_apply_snapshot_and_delta_public_stream() {
+        let mut orderbook = Orderbook::new();
+
+        for message in [SNAPSHOT, DELTA] {
+            let Some(PublicEvent::Orderbook(update)) = parse_public(serde_json::from_str(message).unwrap()).unwrap() else {
+                panic!("Expected an orderbook event")
+            };
+
+            update.apply_to(&mut orderbook).unwrap();
+        }
+
+        // 16493.5 was removed by the delta
+        assert_eq!(orderbook.get_bid().unwrap().price, 16493.0);
+        assert_eq!(orderbook.get_bids().borrow().len(), 2);
+        assert_eq!(orderbook.get_ask().unwrap().size, 0.5);
+        assert_eq!(orderbook.last_update_time(), 1672304484990);
+
+        // A new snapshot replaces the book
+        let Some(PublicEvent::Orderbook(update)) = parse_public(serde_json::from_str(SNAPSHOT).unwrap()).unwrap() else {
+            panic!("Expected an orderbook event")
+        };
+
+        update.apply_to(&mut orderbook).unwrap();
+
+        assert_eq!(orderbook.get_bid().unwrap().price, 16493.5);
+        assert_eq!(orderbook.get_bids().borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_trades_tickers_klines_public_stream() {
+        let trades = json!({
+            "topic": "publicTrade.BTCUSDT",
+            "type": "snapshot",
+            "ts": 1672304486868u64,
+            "data": [{
+                "T": 1672304486865u64, "s": "BTCUSDT", "S": "Buy", "v": "0.001", "p": "16578.50",
+                "L": "PlusTick", "i": "20f43950-d8dd-5b31-9112-a178eb6023af", "BT": false
+            }]
+        });
+
+        let Some(PublicEvent::Trades(trades)) = parse_public(trades).unwrap() else { panic!("Expected trades") };
+        assert_eq!(trades[0].side, Side::Buy);
+        assert_eq!(trades[0].price, 16578.5);
+        assert_eq!(trades[0].time, 1672304486865);
+
+        let ticker = json!({
+            "topic": "tickers.BTCUSDT",
+            "type": "delta",
+            "ts": 1673853746003u64,
+            "cs": 2588407389u64,
+            "data": { "symbol": "BTCUSDT", "bid1Price": "21109.77", "bid1Size": "0.1", "markPrice": "21109.9" }
+        });
+
+        let Some(PublicEvent::Ticker(ticker)) = parse_public(ticker).unwrap() else { panic!("Expected a ticker") };
+        assert_eq!(ticker.bid1_price, Some(21109.77));
+        assert_eq!(ticker.last_price, None);
+
+        let kline = json!({
+            "topic": "kline.5.BTCUSDT",
+            "type": "snapshot",
+            "ts": 1672324988882u64,
+            "data": [{
+                "start": 1672324800000u64, "end": 1672325099999u64, "interval": "5",
+                "open": "16649.5", "close": "16677", "high": "16677", "low": "16608",
+                "volume": "2.081", "turnover": "34666.4005", "confirm": false, "timestamp": 1672324988882u64
+            }]
+        });
+
+        let Some(PublicEvent::Kline { symbol, klines }) = parse_public(kline).unwrap() else { panic!("Expected klines") };
+        assert_eq!(symbol, "BTCUSDT");
+        assert_eq!(klines[0].interval, Interval::Min5);
+        assert!(!klines[0].confirm);
+
+        let unknown = json!({ "topic": "liquidation.BTCUSDT", "ts": 1u64, "data": {} });
+        assert_eq!(parse_public(unknown).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_resubscribes_after_reconnect_public_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        // Serves one snapshot per connection then drops it, reporting each subscribe frame
+        let (frames_tx, mut frames) = tokio::sync::mpsc::unbounded_channel::<Value>();
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(socket).await.unwrap();
+
+                let Some(Ok(Message::Text(frame))) = ws.next().await else { continue };
+                frames_tx.send(serde_json::from_str(&frame).unwrap()).unwrap();
+
+                ws.send(Message::Text(SNAPSHOT.to_string())).await.unwrap();
+                let _ = ws.close(None).await;
+            }
+        });
+
+        let topics = [Topic::Orderbook { depth: 50, symbol: "BTCUSDT".to_string() }];
+        let (handle, mut events) = PublicStream::connect(url, &topics, fast_config());
+
+        for _ in 0..2 {
+            assert_eq!(next_event(&mut events).await, StreamEvent::Connected);
+            assert!(matches!(next_event(&mut events).await, StreamEvent::Message(PublicEvent::Orderbook(_))));
+            assert_eq!(next_event(&mut events).await, StreamEvent::Disconnected);
+
+            let frame = frames.recv().await.unwrap();
+            assert_eq!(frame, json!({ "op": "subscribe", "args": ["orderbook.50.BTCUSDT"] }));
+        }
+
+        handle.close().await;
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{ Duration, Instant };
+use reqwest::header::{ HeaderMap, HeaderValue };
+use rust_workshop::executor::ExecutorError;
+use rust_workshop::ratelimit::{ EndpointGroup, RateLimitPolicy, RateLimiter };
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_group_from_path_ratelimit() {
+        assert_eq!(EndpointGroup::from_path("/v5/market/kline"), EndpointGroup::MarketData);
+        assert_eq!(EndpointGroup::from_path("/v5/order/create"), EndpointGroup::OrderCreate);
+        assert_eq!(EndpointGroup::from_path("/v5/order/amend-batch"), EndpointGroup::OrderCreate);
+        assert_eq!(EndpointGroup::from_path("/v5/order/cancel-all"), EndpointGroup::OrderCancel);
+        assert_eq!(EndpointGroup::from_path("/v5/position/list"), EndpointGroup::Account);
+    }
+
+    #[test]
+    fn test_try_acquire_exhausts_bucket_ratelimit() {
+        let limiter = RateLimiter::new(RateLimitPolicy::FailFast)
+            .limit(EndpointGroup::OrderCreate, 2, 1.0)
+            .unwrap();
+
+        assert!(limiter.try_acquire(EndpointGroup::OrderCreate).is_ok());
+        assert!(limiter.try_acquire(EndpointGroup::OrderCreate).is_ok());
+
+        let wait = limiter.try_acquire(EndpointGroup::OrderCreate).unwrap_err();
+        assert!(wait > Duration::from_millis(900));
+
+        // Other groups are unaffected
+        assert!(limiter.try_acquire(EndpointGroup::OrderCancel).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_fail_fast_ratelimit() {
+        let limiter = RateLimiter::new(RateLimitPolicy::FailFast)
+            .limit(EndpointGroup::Account, 1, 1.0)
+            .unwrap();
+
+        assert!(limiter.acquire(EndpointGroup::Account).await.is_ok());
+
+        let error = limiter.acquire(EndpointGroup::Account).await.unwrap_err();
+        assert!(matches!(error, ExecutorError::RateLimited { code: None, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_refill_ratelimit() {
+        let limiter = RateLimiter::new(RateLimitPolicy::Wait)
+            .limit(EndpointGroup::MarketData, 1, 20.0)
+            .unwrap();
+
+        let start = Instant::now();
+
+        limiter.acquire(EndpointGroup::MarketData).await.unwrap();
+        limiter.acquire(EndpointGroup::MarketData).await.unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_update_from_headers_ratelimit() {
+        let limiter = RateLimiter::new(RateLimitPolicy::FailFast);
+
+        // The exchange's clock, nowhere near the local one
+        let now_ms = 1_746_270_400_000u64;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Bapi-Limit", HeaderValue::from(20));
+        headers.insert("X-Bapi-Limit-Status", HeaderValue::from(3));
+
+        limiter.update_from_headers(EndpointGroup::OrderCreate, &headers, now_ms as u128);
+        assert!(limiter.available(EndpointGroup::OrderCreate) <= 3.1);
+
+        headers.insert("X-Bapi-Limit-Status", HeaderValue::from(0));
+        headers.insert("X-Bapi-Limit-Reset-Timestamp", HeaderValue::from(now_ms + 5_000));
+
+        limiter.update_from_headers(EndpointGroup::OrderCreate, &headers, now_ms as u128);
+
+        assert_eq!(limiter.available(EndpointGroup::OrderCreate), 0.0);
+        let wait = limiter.try_acquire(EndpointGroup::OrderCreate).unwrap_err();
+        assert!(wait > Duration::from_secs(4) && wait <= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_invalid_limit_ratelimit() {
+        let limiter = || RateLimiter::new(RateLimitPolicy::FailFast);
+
+        assert!(matches!(limiter().limit(EndpointGroup::Account, 1, 0.0), Err(ExecutorError::InvalidRequest(_))));
+        assert!(matches!(limiter().limit(EndpointGroup::Account, 1, f64::NAN), Err(ExecutorError::InvalidRequest(_))));
+        assert!(matches!(limiter().limit(EndpointGroup::Account, 1, f64::INFINITY), Err(ExecutorError::InvalidRequest(_))));
+        assert!(matches!(limiter().limit(EndpointGroup::Account, 0, 1.0), Err(ExecutorError::InvalidRequest(_))));
+    }
+}
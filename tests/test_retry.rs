@@ -0,0 +1,237 @@
+use url::Url;
+use std::sync::{ Arc, Mutex };
+use std::time::Duration;
+use std::collections::VecDeque;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+use rust_workshop::executor::{ BuildRequest, Executor, ExecutorError };
+use rust_workshop::retry::{ Endpoint, Failover, RetryPolicy };
+
+const OK_BODY: &str = r#"{"retCode":0,"retMsg":"OK","result":{},"retExtInfo":{},"time":1}"#;
+
+// Serves the queued (status, body) responses in order, one per connection
+async fn stub_server(responses: Vec<(u16, &'static str)>) -> (Url, Arc<Mutex<usize>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+    let hits = Arc::new(Mutex::new(0));
+    let queue = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+    let server_hits = hits.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+
+            *server_hits.lock().unwrap() += 1;
+
+            let (status, body) = queue
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or((200, OK_BODY));
+
+            let resp = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+
+            let _ = socket.write_all(resp.as_bytes()).await;
+        }
+    });
+
+    (url, hits)
+}
+
+// Returns a local url nothing is listening on
+async fn dead_endpoint() -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+
+    drop(listener);
+    url
+}
+
+fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5)
+    }
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_bounds_retry() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1_000)
+        };
+
+        for _ in 0..100 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let third = policy.backoff(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+
+            let capped = policy.backoff(10);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_millis(1_000));
+        }
+
+        assert_eq!(RetryPolicy::none().max_retries, 0);
+    }
+
+    #[test]
+    fn test_failover_select_retry() {
+        let failover = Failover::new(2, Duration::from_millis(50));
+
+        assert_eq!(failover.select(), Endpoint::Primary);
+
+        failover.record_failure(Endpoint::Primary);
+        assert_eq!(failover.select(), Endpoint::Primary);
+
+        failover.record_failure(Endpoint::Primary);
+        assert_eq!(failover.select(), Endpoint::Alternate);
+        assert!(!failover.is_healthy(Endpoint::Primary));
+
+        // Fails back once the cooldown has passed
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(failover.select(), Endpoint::Primary);
+
+        failover.record_success(Endpoint::Primary);
+        assert!(failover.is_healthy(Endpoint::Primary));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retries_5xx_retry() {
+        let (url, hits) = stub_server(vec![(503, "unavailable"), (502, "bad gateway")]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(fast_retries());
+
+        let resp = executor
+            .fetch::<Value, _>(BuildRequest::get("/v5/market/time"))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.ret_code, 0);
+        assert_eq!(*hits.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_fails_over_to_alt_endpoint_retry() {
+        let primary = dead_endpoint().await;
+        let (alternate, hits) = stub_server(vec![]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), primary, alternate)
+            .with_retry_policy(fast_retries());
+
+        let resp = executor
+            .fetch::<Value, _>(BuildRequest::get("/v5/market/time"))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.ret_code, 0);
+        assert_eq!(*hits.lock().unwrap(), 1);
+        assert!(!executor.failover().is_healthy(Endpoint::Primary));
+        assert_eq!(executor.failover().select(), Endpoint::Alternate);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_does_not_retry_unsafe_post_retry() {
+        let (url, hits) = stub_server(vec![(503, "unavailable")]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(fast_retries());
+
+        let body = serde_json::json!({ "category": "linear", "symbol": "BTCUSDT", "side": "Buy" });
+
+        let error = executor
+            .fetch::<Value, _>(BuildRequest::post("/v5/order/create").body(body).private())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ExecutorError::Status { status: 503, .. }));
+        assert_eq!(*hits.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retries_post_with_order_link_id_retry() {
+        let (url, hits) = stub_server(vec![(503, "unavailable")]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(fast_retries());
+
+        let body = serde_json::json!({ "category": "linear", "symbol": "BTCUSDT", "orderLinkId": "quote-1" });
+
+        let resp = executor
+            .fetch::<Value, _>(BuildRequest::post("/v5/order/create").body(body).private())
+            .await
+            .unwrap();
+
+        assert_eq!(resp.ret_code, 0);
+        assert_eq!(*hits.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_order_link_id_after_retry_retry() {
+        let duplicate = r#"{"retCode":110072,"retMsg":"OrderLinkedID is duplicate","result":{},"retExtInfo":{},"time":1}"#;
+        let (url, hits) = stub_server(vec![(503, "unavailable"), (200, duplicate), (200, duplicate)]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(fast_retries());
+
+        let body = serde_json::json!({ "category": "linear", "symbol": "BTCUSDT", "orderLinkId": "quote-1" });
+
+        let error = executor
+            .fetch::<Value, _>(BuildRequest::post("/v5/order/create").body(body.clone()).private())
+            .await
+            .unwrap_err();
+
+        // The first attempt may have gone through before the 503
+        assert!(matches!(error, ExecutorError::MaybeExecuted(_)));
+        assert_eq!(error.code(), Some(110072));
+        assert_eq!(*hits.lock().unwrap(), 2);
+
+        // Without a retry the duplicate is a plain rejection
+        let error = executor
+            .fetch::<Value, _>(BuildRequest::post("/v5/order/create").body(body).private())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ExecutorError::Exchange { code: 110072, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_by_order_link_id_not_retried_retry() {
+        let not_exists = r#"{"retCode":110001,"retMsg":"order not exists or too late to cancel","result":{},"retExtInfo":{},"time":1}"#;
+        let (url, hits) = stub_server(vec![(503, "unavailable"), (200, not_exists)]).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(fast_retries());
+
+        let body = serde_json::json!({ "category": "linear", "symbol": "BTCUSDT", "orderLinkId": "quote-1" });
+
+        let error = executor
+            .fetch::<Value, _>(BuildRequest::post("/v5/order/cancel").body(body).private())
+            .await
+            .unwrap_err();
+
+        // The cancel may have gone through before the 503, a retry would report it as missing
+        assert!(matches!(error, ExecutorError::Status { status: 503, .. }));
+        assert_eq!(*hits.lock().unwrap(), 1);
+    }
+}
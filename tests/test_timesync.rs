@@ -0,0 +1,131 @@
+use url::Url;
+use std::sync::{ Arc, Mutex };
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+use rust_workshop::executor::{ decode_response, ApiResponse, Executor };
+use rust_workshop::market::ServerTime;
+use rust_workshop::retry::RetryPolicy;
+use rust_workshop::timesync::{ local_time_ms, ClockSample, TimeSync };
+
+// Answers every connection with the same status, counting the requests
+async fn stub_server(status: u16) -> (Url, Arc<Mutex<usize>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+    let hits = Arc::new(Mutex::new(0));
+
+    let server_hits = hits.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+
+            *server_hits.lock().unwrap() += 1;
+
+            let resp = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            let _ = socket.write_all(resp.as_bytes()).await;
+        }
+    });
+
+    (url, hits)
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_sample_timesync() {
+        // Local clock is 500ms behind, 40ms round trip
+        let sample = ClockSample::new(1_000, 1_520, 1_040);
+
+        assert_eq!(sample.round_trip_ms, 40);
+        assert_eq!(sample.latency_ms(), 20);
+        assert_eq!(sample.offset_ms, 500);
+
+        // Local clock is 300ms ahead
+        let sample = ClockSample::new(10_000, 9_710, 10_020);
+        assert_eq!(sample.offset_ms, -300);
+    }
+
+    #[test]
+    fn test_estimate_prefers_fastest_round_trip_timesync() {
+        let time_sync = TimeSync::new();
+
+        assert_eq!(time_sync.estimate(), None);
+        assert_eq!(time_sync.offset_ms(), 0);
+        assert_eq!(time_sync.latency_ms(), None);
+
+        time_sync.record(ClockSample { offset_ms: 250, round_trip_ms: 300 });
+        let best = time_sync.record(ClockSample { offset_ms: 100, round_trip_ms: 20 });
+        time_sync.record(ClockSample { offset_ms: 180, round_trip_ms: 90 });
+
+        assert_eq!(best.offset_ms, 100);
+        assert_eq!(time_sync.offset_ms(), 100);
+        assert_eq!(time_sync.latency_ms(), Some(10));
+    }
+
+    #[test]
+    fn test_estimate_forgets_old_samples_timesync() {
+        let time_sync = TimeSync::new();
+
+        time_sync.record(ClockSample { offset_ms: 100, round_trip_ms: 1 });
+
+        for _ in 0..8 {
+            time_sync.record(ClockSample { offset_ms: 200, round_trip_ms: 50 });
+        }
+
+        assert_eq!(time_sync.offset_ms(), 200);
+    }
+
+    #[test]
+    fn test_now_ms_applies_offset_timesync() {
+        let time_sync = TimeSync::new();
+        time_sync.record(ClockSample { offset_ms: 60_000, round_trip_ms: 10 });
+
+        let local = local_time_ms();
+        let corrected = time_sync.now_ms();
+
+        assert!(corrected >= local + 60_000);
+        assert!(corrected < local + 61_000);
+    }
+
+    #[test]
+    fn test_decode_server_time_timesync() {
+        let body = r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": { "timeSecond": "1688639403", "timeNano": "1688639403423213947" },
+            "retExtInfo": {},
+            "time": 1688639403423
+        }"#;
+
+        let response: ApiResponse<ServerTime> = decode_response(200, body).unwrap();
+
+        assert_eq!(response.result.time_second, 1688639403);
+        assert_eq!(response.result.time_nano / 1_000_000, 1688639403423);
+    }
+
+    #[tokio::test]
+    async fn test_sync_time_is_a_single_attempt_timesync() {
+        let (url, hits) = stub_server(503).await;
+
+        let executor = Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+            .with_retry_policy(RetryPolicy {
+                max_retries: 3,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5)
+            });
+
+        // A retried round trip would skew the offset, the sample is dropped instead
+        assert!(executor.sync_time().await.is_err());
+        assert_eq!(*hits.lock().unwrap(), 1);
+        assert_eq!(executor.time_sync().estimate(), None);
+    }
+}
@@ -0,0 +1,184 @@
+use url::Url;
+use std::time::Duration;
+use serde_json::{ json, Value };
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::UnboundedReceiver;
+use futures_util::{ SinkExt, StreamExt };
+use tokio_tungstenite::{ accept_async, tungstenite::Message };
+use rust_workshop::executor::{ decode_envelope, Executor, ExecutorError };
+use rust_workshop::instrument::{ InstrumentSpec, Price, Qty };
+use rust_workshop::market::{ Category, Side };
+use rust_workshop::orders::{ OrderAck, PlaceOrderRequest };
+use rust_workshop::trade_stream::{ OrderTransport, TradeConfig, TradeResponse, TradeSession };
+
+fn executor() -> Executor {
+    let url = Url::parse("https://api-testnet.bybit.com").unwrap();
+
+    Executor::new("key".to_string(), "secret".to_string(), url.clone(), url)
+}
+
+// Ticks of 0.1 and lots of 0.001 under any symbol
+fn spec(symbol: &str) -> InstrumentSpec {
+    InstrumentSpec::new(symbol, 0.1, 0.001).unwrap()
+}
+
+fn fast_config() -> TradeConfig {
+    TradeConfig {
+        request_timeout: Duration::from_millis(200),
+        ..TradeConfig::default()
+    }
+}
+
+// Fake /v5/trade endpoint, the order symbol picks the reply:
+// "SILENT" never answers, "DROP" closes the connection, "POOR" is rejected, anything else is accepted
+async fn trade_server() -> (Url, UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+    let (frames_tx, frames) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+
+            while let Some(Ok(Message::Text(frame))) = ws.next().await {
+                let frame: Value = serde_json::from_str(&frame).unwrap();
+                let _ = frames_tx.send(frame.clone());
+
+                let reply = match frame["op"].as_str().unwrap() {
+                    "auth" => json!({ "retCode": 0, "retMsg": "OK", "op": "auth", "connId": "1" }),
+
+                    op => match frame["args"][0]["symbol"].as_str().unwrap_or_default() {
+                        "SILENT" => continue,
+                        "DROP" => break,
+                        "POOR" => json!({
+                            "reqId": frame["reqId"], "retCode": 110007, "retMsg": "ab not enough for new order",
+                            "op": op, "data": {}, "header": { "Timenow": "1711001595207" }
+                        }),
+                        _ => json!({
+                            "reqId": frame["reqId"], "retCode": 0, "retMsg": "OK", "op": op,
+                            "data": { "orderId": format!("ws-{}", frame["reqId"].as_str().unwrap()), "orderLinkId": "" },
+                            "retExtInfo": {}, "header": { "Timenow": "1711001595207" }, "connId": "1"
+                        })
+                    }
+                };
+
+                ws.send(Message::Text(reply.to_string())).await.unwrap();
+            }
+        }
+    });
+
+    (url, frames)
+}
+
+async fn ws_executor() -> (Executor, UnboundedReceiver<Value>) {
+    let (url, frames) = trade_server().await;
+    let executor = executor();
+    let session = TradeSession::connect(&executor, url, fast_config());
+
+    tokio::time::timeout(Duration::from_secs(5), session.wait_connected())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let executor = executor
+        .with_trade_session(session)
+        .with_order_transport(OrderTransport::WebSocket);
+
+    (executor, frames)
+}
+
+/*
+TESTS ARE HERE
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_trade_response_trade_stream() {
+        let response: TradeResponse = serde_json::from_value(json!({
+            "reqId": "test-005",
+            "retCode": 0,
+            "retMsg": "OK",
+            "op": "order.create",
+            "data": { "orderId": "a4c1718f-fe53-4659-a225-d1a6fd3dcf17", "orderLinkId": "" },
+            "retExtInfo": {},
+            "header": { "X-Bapi-Limit": "10", "X-Bapi-Limit-Status": "9", "Timenow": "1711001595207" },
+            "connId": "cnhrkularu5bv93alse0-5"
+        })).unwrap();
+
+        assert_eq!(response.req_id, "test-005");
+
+        let envelope = response.into_envelope();
+        assert_eq!(envelope.time, 1711001595207);
+
+        let ack = decode_envelope::<OrderAck>(envelope).unwrap().result;
+        assert_eq!(ack.order_id, "a4c1718f-fe53-4659-a225-d1a6fd3dcf17");
+    }
+
+    #[tokio::test]
+    async fn test_place_order_over_websocket_trade_stream() {
+        let (executor, mut frames) = ws_executor().await;
+
+        let request = PlaceOrderRequest::limit(&spec("BTCUSDT"), Side::Buy, Qty(10), Price(300000)).order_link_id("quote-1");
+        let first = executor.place_order(Category::Linear, &request).await.unwrap();
+        let second = executor.place_order(Category::Linear, &request).await.unwrap();
+
+        // Every request gets its own reqId and the response is routed back to it
+        assert_ne!(first.order_id, second.order_id);
+
+        let auth = frames.recv().await.unwrap();
+        assert_eq!(auth["op"], "auth");
+
+        let frame = frames.recv().await.unwrap();
+        assert_eq!(frame["op"], "order.create");
+        assert_eq!(format!("ws-{}", frame["reqId"].as_str().unwrap()), first.order_id);
+        assert_eq!(frame["header"]["X-BAPI-RECV-WINDOW"], "5000");
+        assert!(frame["header"]["X-BAPI-TIMESTAMP"].as_str().unwrap().parse::<u64>().is_ok());
+        assert_eq!(frame["args"][0]["category"], "linear");
+        assert_eq!(frame["args"][0]["orderLinkId"], "quote-1");
+    }
+
+    #[tokio::test]
+    async fn test_exchange_rejection_trade_stream() {
+        let (executor, _frames) = ws_executor().await;
+
+        let request = PlaceOrderRequest::market(&spec("POOR"), Side::Buy, Qty(1000));
+        let error = executor.place_order(Category::Spot, &request).await.unwrap_err();
+
+        assert_eq!(error.code(), Some(110007));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_and_disconnect_trade_stream() {
+        let (executor, _frames) = ws_executor().await;
+
+        let silent = PlaceOrderRequest::market(&spec("SILENT"), Side::Buy, Qty(1000));
+        let error = executor.place_order(Category::Spot, &silent).await.unwrap_err();
+        assert!(matches!(error, ExecutorError::Timeout));
+
+        let dropped = PlaceOrderRequest::market(&spec("DROP"), Side::Buy, Qty(1000));
+        let error = executor.place_order(Category::Spot, &dropped).await.unwrap_err();
+        assert!(matches!(error, ExecutorError::Stream(_)));
+
+        // The session reconnects and carries on
+        let session = executor.trade_session().unwrap();
+        tokio::time::timeout(Duration::from_secs(5), session.wait_connected()).await.unwrap().unwrap();
+
+        let request = PlaceOrderRequest::market(&spec("BTCUSDT"), Side::Buy, Qty(1000));
+        assert!(executor.place_order(Category::Spot, &request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_transport_requires_session_trade_stream() {
+        let executor = executor().with_order_transport(OrderTransport::WebSocket);
+
+        let request = PlaceOrderRequest::market(&spec("BTCUSDT"), Side::Buy, Qty(1000));
+        let error = executor.place_order(Category::Spot, &request).await.unwrap_err();
+
+        assert!(matches!(error, ExecutorError::InvalidRequest(_)));
+        assert_eq!(executor.order_transport(), OrderTransport::WebSocket);
+    }
+}